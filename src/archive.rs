@@ -0,0 +1,310 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Component, Path};
+use xz2::read::XzDecoder;
+use xz2::write::XzEncoder;
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// Longest symlink target read from a ZIP entry
+const MAX_LINK_TARGET: u64 = 4096;
+
+/// Archive formats accepted as templates, detected from their content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+}
+
+impl ArchiveFormat {
+    pub const ALL: [ArchiveFormat; 5] = [
+        ArchiveFormat::Zip,
+        ArchiveFormat::Tar,
+        ArchiveFormat::TarGz,
+        ArchiveFormat::TarXz,
+        ArchiveFormat::TarZst,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::Tar => "tar",
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::TarXz => "tar.xz",
+            ArchiveFormat::TarZst => "tar.zst",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ArchiveFormat> {
+        match value.to_lowercase().as_str() {
+            "tgz" => Some(ArchiveFormat::TarGz),
+            "txz" => Some(ArchiveFormat::TarXz),
+            "tzst" => Some(ArchiveFormat::TarZst),
+            value => ArchiveFormat::ALL.into_iter().find(|f| f.as_str() == value),
+        }
+    }
+
+    /// Identify an archive by its magic bytes, whatever its file name.
+    /// Returns `None` for files that are not a supported archive.
+    pub fn detect(path: &Path) -> io::Result<Option<ArchiveFormat>> {
+        let mut header = Vec::with_capacity(512);
+        File::open(path)?.take(512).read_to_end(&mut header)?;
+        Ok(ArchiveFormat::from_magic(&header))
+    }
+
+    fn from_magic(header: &[u8]) -> Option<ArchiveFormat> {
+        if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+            Some(ArchiveFormat::Zip)
+        } else if header.starts_with(&[0x1f, 0x8b]) {
+            Some(ArchiveFormat::TarGz)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(ArchiveFormat::TarXz)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(ArchiveFormat::TarZst)
+        } else if header.get(257..262) == Some(b"ustar".as_slice()) {
+            Some(ArchiveFormat::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Type of an archive entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink(String),
+    /// Hard links, devices, FIFOs…: never extracted
+    Other,
+}
+
+/// One entry of an archive, as read by [`for_each_entry`]
+pub struct ArchiveEntry<'a> {
+    /// Name as stored in the archive (`/`-separated, directories may end with `/`)
+    pub name: String,
+    pub kind: EntryKind,
+    /// Unix permission bits recorded in the archive
+    pub mode: Option<u32>,
+    /// Uncompressed size declared by the archive headers (may lie)
+    pub size: u64,
+    /// Whether the name is relative and has no `..` component
+    pub enclosed: bool,
+    /// Content of a file entry
+    pub reader: &'a mut dyn Read,
+}
+
+/// Call `f` on every entry of an archive, in archive order, until it returns `Ok(false)`
+pub fn for_each_entry<F>(path: &Path, format: ArchiveFormat, mut f: F) -> io::Result<()>
+where
+    F: FnMut(ArchiveEntry<'_>) -> io::Result<bool>,
+{
+    let file = File::open(path)?;
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::Zip => return for_each_zip_entry(file, f),
+        ArchiveFormat::Tar => Box::new(BufReader::new(file)),
+        ArchiveFormat::TarGz => Box::new(GzDecoder::new(BufReader::new(file))),
+        ArchiveFormat::TarXz => Box::new(XzDecoder::new(BufReader::new(file))),
+        ArchiveFormat::TarZst => Box::new(zstd::Decoder::new(file)?),
+    };
+
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+        let header = entry.header();
+        let kind = match header.entry_type() {
+            tar::EntryType::Regular | tar::EntryType::Continuous => EntryKind::File,
+            tar::EntryType::Directory => EntryKind::Dir,
+            tar::EntryType::Symlink => EntryKind::Symlink(
+                entry.link_name_bytes().map(|t| String::from_utf8_lossy(&t).to_string()).unwrap_or_default(),
+            ),
+            _ => EntryKind::Other,
+        };
+        let mode = header.mode().ok().map(|m| m & 0o777);
+        let size = header.size().unwrap_or(0);
+        let enclosed = is_enclosed(&name);
+        if !f(ArchiveEntry { name, kind, mode, size, enclosed, reader: &mut entry })? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn for_each_zip_entry<F>(file: File, mut f: F) -> io::Result<()>
+where
+    F: FnMut(ArchiveEntry<'_>) -> io::Result<bool>,
+{
+    let mut archive = ZipArchive::new(file)?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let name = entry.name().to_string();
+        let enclosed = entry.enclosed_name().is_some();
+        let mode = entry.unix_mode();
+        let size = entry.size();
+        let kind = if entry.is_dir() {
+            EntryKind::Dir
+        } else if mode.is_some_and(|m| m & 0o170000 == 0o120000) {
+            // the target of a symlink is stored as the content of the entry
+            let mut target = String::new();
+            (&mut entry).take(MAX_LINK_TARGET).read_to_string(&mut target)?;
+            EntryKind::Symlink(target)
+        } else {
+            EntryKind::File
+        };
+        let mode = mode.map(|m| m & 0o777);
+        if !f(ArchiveEntry { name, kind, mode, size, enclosed, reader: &mut entry })? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Same rule as the zip crate's `enclosed_name`: relative, without `..`
+fn is_enclosed(name: &str) -> bool {
+    !name.contains('\0')
+        && Path::new(name)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// Compressed tar stream being written
+enum TarSink {
+    Plain(File),
+    Gz(GzEncoder<File>),
+    Xz(XzEncoder<File>),
+    Zst(zstd::Encoder<'static, File>),
+}
+
+impl Write for TarSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            TarSink::Plain(w) => w.write(buf),
+            TarSink::Gz(w) => w.write(buf),
+            TarSink::Xz(w) => w.write(buf),
+            TarSink::Zst(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TarSink::Plain(w) => w.flush(),
+            TarSink::Gz(w) => w.flush(),
+            TarSink::Xz(w) => w.flush(),
+            TarSink::Zst(w) => w.flush(),
+        }
+    }
+}
+
+impl TarSink {
+    fn finish(self) -> io::Result<File> {
+        match self {
+            TarSink::Plain(w) => Ok(w),
+            TarSink::Gz(w) => w.finish(),
+            TarSink::Xz(w) => w.finish(),
+            TarSink::Zst(w) => w.finish(),
+        }
+    }
+}
+
+/// Writer of template archives in any [`ArchiveFormat`]
+pub struct ArchiveWriter {
+    inner: Writer,
+}
+
+enum Writer {
+    Zip(Box<ZipWriter<File>>),
+    Tar(Box<tar::Builder<TarSink>>),
+}
+
+impl ArchiveWriter {
+    pub fn create(path: &Path, format: ArchiveFormat) -> io::Result<ArchiveWriter> {
+        let file = File::create(path)?;
+        let sink = match format {
+            ArchiveFormat::Zip => {
+                return Ok(ArchiveWriter { inner: Writer::Zip(Box::new(ZipWriter::new(file))) })
+            }
+            ArchiveFormat::Tar => TarSink::Plain(file),
+            ArchiveFormat::TarGz => TarSink::Gz(GzEncoder::new(file, Compression::default())),
+            ArchiveFormat::TarXz => TarSink::Xz(XzEncoder::new(file, 6)),
+            ArchiveFormat::TarZst => TarSink::Zst(zstd::Encoder::new(file, 0)?),
+        };
+        let mut builder = tar::Builder::new(sink);
+        builder.mode(tar::HeaderMode::Deterministic);
+        Ok(ArchiveWriter { inner: Writer::Tar(Box::new(builder)) })
+    }
+
+    pub fn add_dir(&mut self, name: &str, mode: Option<u32>) -> io::Result<()> {
+        match &mut self.inner {
+            Writer::Zip(zip) => {
+                zip.add_directory(name, zip_options(mode))?;
+                Ok(())
+            }
+            Writer::Tar(tar) => {
+                let mut header = tar_header(tar::EntryType::Directory, mode.unwrap_or(0o755), 0);
+                tar.append_data(&mut header, format!("{}/", name.trim_end_matches('/')), io::empty())
+            }
+        }
+    }
+
+    pub fn add_file(&mut self, name: &str, data: &[u8], mode: Option<u32>) -> io::Result<()> {
+        match &mut self.inner {
+            Writer::Zip(zip) => {
+                zip.start_file(name, zip_options(mode))?;
+                zip.write_all(data)
+            }
+            Writer::Tar(tar) => {
+                let mut header = tar_header(tar::EntryType::Regular, mode.unwrap_or(0o644), data.len() as u64);
+                tar.append_data(&mut header, name, data)
+            }
+        }
+    }
+
+    pub fn add_symlink(&mut self, name: &str, target: &str) -> io::Result<()> {
+        match &mut self.inner {
+            Writer::Zip(zip) => {
+                zip.add_symlink(name, target, zip_options(None))?;
+                Ok(())
+            }
+            Writer::Tar(tar) => {
+                let mut header = tar_header(tar::EntryType::Symlink, 0o777, 0);
+                tar.append_link(&mut header, name, target)
+            }
+        }
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        match self.inner {
+            Writer::Zip(mut zip) => {
+                zip.finish()?;
+            }
+            Writer::Tar(tar) => {
+                tar.into_inner()?.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn zip_options(mode: Option<u32>) -> FileOptions {
+    let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    match mode {
+        Some(mode) => options.unix_permissions(mode),
+        None => options,
+    }
+}
+
+fn tar_header(entry_type: tar::EntryType, mode: u32, size: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(entry_type);
+    header.set_mode(mode);
+    header.set_size(size);
+    header.set_mtime(0);
+    header
+}
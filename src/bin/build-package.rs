@@ -38,7 +38,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Create ZIP file
     let file = fs::File::create(&zip_filename)
-        .expect(&format!("Failed to create {}", zip_filename));
+        .unwrap_or_else(|_| panic!("Failed to create {}", zip_filename));
     let mut zip = ZipWriter::new(file);
     let options = FileOptions::default()
         .compression_method(zip::CompressionMethod::Deflated)
@@ -49,9 +49,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Add compiled executable
     if Path::new(&exe_path).exists() {
         let exe_data = fs::read(&exe_path)
-            .expect(&format!("Failed to read {}", exe_path));
+            .unwrap_or_else(|_| panic!("Failed to read {}", exe_path));
         
-        zip.start_file(format!("{}{}", package_name, exe_ext), options.clone())
+        zip.start_file(format!("{}{}", package_name, exe_ext), options)
             .expect("Error adding EXE file to ZIP");
         zip.write_all(&exe_data)
             .expect("Error writing EXE file");
@@ -63,21 +63,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Add *.md files from docs directory
     if Path::new("docs").is_dir() {
-        for entry in fs::read_dir("docs").expect("Failed to read docs directory") {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.extension().map_or(false, |ext| ext == "md") {
-                    let filename = path.file_name().unwrap().to_string_lossy();
-                    let file_content = fs::read(&path)
-                        .expect(&format!("Failed to read {}", path.display()));
-                    
-                    zip.start_file(format!("docs/{}", filename), options.clone())
-                        .expect(&format!("Error adding {}", filename));
-                    zip.write_all(&file_content)
-                        .expect(&format!("Error writing {}", filename));
-                    
-                    println!("  ✓ Added: docs/{} ({} bytes)", filename, file_content.len());
-                }
+        for entry in fs::read_dir("docs").expect("Failed to read docs directory").flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "md") {
+                let filename = path.file_name().unwrap().to_string_lossy();
+                let file_content = fs::read(&path)
+                    .unwrap_or_else(|_| panic!("Failed to read {}", path.display()));
+                
+                zip.start_file(format!("docs/{}", filename), options)
+                    .unwrap_or_else(|_| panic!("Error adding {}", filename));
+                zip.write_all(&file_content)
+                    .unwrap_or_else(|_| panic!("Error writing {}", filename));
+                
+                println!("  ✓ Added: docs/{} ({} bytes)", filename, file_content.len());
             }
         }
     } else {
@@ -87,21 +85,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Add template ZIPs from release-templates directory
     if Path::new("target/release-templates").is_dir() {
         println!("📦 Adding templates...");
-        for entry in fs::read_dir("target/release-templates").expect("Failed to read release-templates directory") {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.is_file() && path.extension().map_or(false, |ext| ext == "zip") {
-                    let filename = path.file_name().unwrap().to_string_lossy();
-                    let file_content = fs::read(&path)
-                        .expect(&format!("Failed to read {}", path.display()));
-                    
-                    zip.start_file(format!("templates/{}", filename), options.clone())
-                        .expect(&format!("Error adding {}", filename));
-                    zip.write_all(&file_content)
-                        .expect(&format!("Error writing {}", filename));
-                    
-                    println!("  ✓ Added: templates/{} ({} bytes)", filename, file_content.len());
-                }
+        for entry in fs::read_dir("target/release-templates").expect("Failed to read release-templates directory").flatten() {
+            let path = entry.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "zip") {
+                let filename = path.file_name().unwrap().to_string_lossy();
+                let file_content = fs::read(&path)
+                    .unwrap_or_else(|_| panic!("Failed to read {}", path.display()));
+                
+                zip.start_file(format!("templates/{}", filename), options)
+                    .unwrap_or_else(|_| panic!("Error adding {}", filename));
+                zip.write_all(&file_content)
+                    .unwrap_or_else(|_| panic!("Error writing {}", filename));
+                
+                println!("  ✓ Added: templates/{} ({} bytes)", filename, file_content.len());
             }
         }
     } else {
@@ -1,48 +1,120 @@
+use genj::archive::{ArchiveFormat, ArchiveWriter};
+use genj::fs::file_mode;
+use genj::integrity::{
+    build_manifest, dir_entry, file_entry, generate_keypair, sign_manifest, ManifestEntry, INTEGRITY_DIR, MANIFEST_FILE,
+    SIGNATURE_FILE,
+};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs::File;
-use std::io::{Write, Read, Seek};
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use zip::write::FileOptions;
-use zip::ZipWriter;
 
-fn zip_dir<T: Write + Seek>(
+/// Integrity data to embed in the archive
+struct Integrity<'a> {
+    manifest: bool,
+    sign_key: Option<&'a Path>,
+}
+
+fn archive_dir(
     it: &mut dyn Iterator<Item = PathBuf>,
     prefix: &Path,
-    writer: T,
-) -> zip::result::ZipResult<()> {
-    let mut zip = ZipWriter::new(writer);
-    let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    mut archive: ArchiveWriter,
+    integrity: &Integrity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut entries = BTreeMap::new();
     for path in it {
         let name = path.strip_prefix(prefix).unwrap();
-        if path.is_file() {
-            zip.start_file(name.to_string_lossy(), options)?;
+        // integrity data is regenerated below, never copied from the source folder
+        if name.starts_with(INTEGRITY_DIR) {
+            continue;
+        }
+        let entry_name = name.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        if path.is_symlink() {
+            let target = std::fs::read_link(&path)?.to_string_lossy().replace('\\', "/");
+            archive.add_symlink(&entry_name, &target)?;
+            entries.insert(entry_name, ManifestEntry::Symlink { target });
+        } else if path.is_file() {
             let mut f = File::open(&path)?;
             let mut buffer = Vec::new();
             f.read_to_end(&mut buffer)?;
-            zip.write_all(&buffer)?;
-        } else if path.is_dir() {
-            zip.add_directory(name.to_string_lossy(), options)?;
+            // record the Unix permissions so that executable scripts stay executable
+            archive.add_file(&entry_name, &buffer, file_mode(&path))?;
+            entries.insert(entry_name, file_entry(&buffer, file_mode(&path)));
+        } else if path.is_dir() && !entry_name.is_empty() {
+            archive.add_dir(&entry_name, file_mode(&path))?;
+            entries.insert(entry_name, dir_entry(file_mode(&path)));
         }
     }
-    zip.finish()?;
+
+    if integrity.manifest || integrity.sign_key.is_some() {
+        let manifest = build_manifest(&entries);
+        archive.add_file(MANIFEST_FILE, &manifest, None)?;
+        if let Some(key) = integrity.sign_key {
+            let signature = sign_manifest(&manifest, key)?;
+            archive.add_file(SIGNATURE_FILE, &signature, None)?;
+        }
+    }
+    archive.finish()?;
     Ok(())
 }
 
+fn usage(program: &str) -> ! {
+    eprintln!(
+        "Usage: {} [--format zip|tar|tar.gz|tar.xz|tar.zst] [--manifest] [--sign <secret_key_file>] <src_dir> <dest_archive>",
+        program
+    );
+    eprintln!("       {} --keygen <secret_key_file>", program);
+    std::process::exit(1);
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 3 {
-        eprintln!("Usage: {} <src_dir> <dest_zip>", args[0]);
-        std::process::exit(1);
+    let mut integrity = Integrity { manifest: false, sign_key: None };
+    let mut format = ArchiveFormat::Zip;
+    let mut positional = Vec::new();
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--manifest" => integrity.manifest = true,
+            "--format" => {
+                i += 1;
+                format = args.get(i).and_then(|f| ArchiveFormat::parse(f)).unwrap_or_else(|| usage(&args[0]));
+            }
+            "--sign" => {
+                i += 1;
+                integrity.sign_key = Some(Path::new(args.get(i).unwrap_or_else(|| usage(&args[0]))));
+            }
+            "--keygen" => {
+                let key_file = Path::new(args.get(i + 1).unwrap_or_else(|| usage(&args[0])));
+                let public = generate_keypair(key_file)?;
+                println!("Secret key written to {}", key_file.display());
+                println!("Public key (add it to trusted_keys): {}", public);
+                return Ok(());
+            }
+            _ => positional.push(args[i].as_str()),
+        }
+        i += 1;
     }
-    let src_dir = Path::new(&args[1]);
-    let dest_zip = &args[2];
-    let file = File::create(dest_zip)?;
+    if positional.len() != 2 {
+        usage(&args[0]);
+    }
+
+    let src_dir = Path::new(positional[0]);
+    let dest_archive = positional[1];
     let mut paths = Vec::new();
     for entry in walkdir::WalkDir::new(src_dir) {
         let entry = entry?;
         paths.push(entry.path().to_path_buf());
     }
-    zip_dir(&mut paths.into_iter(), src_dir, file)?;
-    println!("Zipped {} -> {}", src_dir.display(), dest_zip);
+    let archive = ArchiveWriter::create(Path::new(dest_archive), format)?;
+    archive_dir(&mut paths.into_iter(), src_dir, archive, &integrity)?;
+    println!("Archived {} -> {} ({})", src_dir.display(), dest_archive, format.as_str());
+    if integrity.sign_key.is_some() {
+        println!("Embedded signed manifest: {}", MANIFEST_FILE);
+    } else if integrity.manifest {
+        println!("Embedded manifest: {}", MANIFEST_FILE);
+    }
     Ok(())
 }
@@ -0,0 +1,443 @@
+use crate::archive::ArchiveFormat;
+use crate::cli::OutputFormat;
+use crate::query::{Field, Query, SearchHit};
+use crate::index::TemplateIndex;
+use crate::log::{log_success, log_warning};
+use crate::registry::{fetch_index, registry_urls, REGISTRY_PREFIX};
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+/// System-wide template directory
+pub const SYSTEM_TEMPLATES_DIR: &str = "/usr/share/genj/templates";
+/// Environment variable listing additional template directories (`:`-separated)
+pub const TEMPLATE_PATH_ENV: &str = "GENJ_TEMPLATE_PATH";
+
+/// Metadata fields displayed (in this order) for each template
+const METADATA_LABELS: [(&str, &str, Field); 6] = [
+    ("description", "Description", Field::Description),
+    ("language", "Language", Field::Language),
+    ("version", "Version", Field::Version),
+    ("author", "Author", Field::Author),
+    ("contact", "Contact", Field::Contact),
+    ("license", "License", Field::License),
+];
+
+/// Storage kind of a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateKind {
+    Archive(ArchiveFormat),
+    Dir,
+    /// Published in a registry, downloaded on use
+    Registry,
+}
+
+impl TemplateKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TemplateKind::Archive(format) => format.as_str(),
+            TemplateKind::Dir => "dir",
+            TemplateKind::Registry => "registry",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<TemplateKind> {
+        match value {
+            "dir" => Some(TemplateKind::Dir),
+            "registry" => Some(TemplateKind::Registry),
+            format => ArchiveFormat::parse(format).map(TemplateKind::Archive),
+        }
+    }
+}
+
+/// Where the templates of a source come from
+#[derive(Debug, Clone)]
+pub enum SourceLocation {
+    /// A local directory holding template archives and template folders
+    Dir(PathBuf),
+    /// The `index.json` URL of a template registry
+    Registry(String),
+}
+
+/// A location scanned for templates
+#[derive(Debug, Clone)]
+pub struct TemplateSource {
+    /// `system`, `user`, or the directory or URL itself for custom locations and registries
+    pub name: String,
+    /// Header shown in table output
+    pub title: String,
+    pub location: SourceLocation,
+}
+
+impl TemplateSource {
+    fn dir(&self) -> Option<&Path> {
+        match &self.location {
+            SourceLocation::Dir(dir) => Some(dir),
+            SourceLocation::Registry(_) => None,
+        }
+    }
+
+    /// Local directories must exist, registries are always queried
+    fn is_available(&self) -> bool {
+        self.dir().is_none_or(Path::exists)
+    }
+}
+
+/// A template found in one of the template sources
+#[derive(Debug, Clone)]
+pub struct TemplateEntry {
+    pub name: String,
+    pub source: String,
+    pub kind: TemplateKind,
+    pub path: PathBuf,
+    /// Download URL of registry templates
+    pub url: Option<String>,
+    pub metadata: Option<Value>,
+}
+
+impl TemplateEntry {
+    fn to_json(&self, hit: Option<&SearchHit>) -> Value {
+        let mut record = json!({
+            "name": self.name,
+            "source": self.source,
+            "kind": self.kind.as_str(),
+            "path": absolute(&self.path).display().to_string(),
+            "metadata": self.metadata,
+        });
+        if let Some(url) = &self.url {
+            record["url"] = json!(url);
+        }
+        if let Some(hit) = hit {
+            record["score"] = json!(hit.score);
+            record["matches"] = hit
+                .matches
+                .iter()
+                .map(|m| json!({ "field": m.field.as_str(), "value": m.value, "exact": m.exact }))
+                .collect();
+        }
+        record
+    }
+}
+
+/// Template sources in search order: system, user, `GENJ_TEMPLATE_PATH` entries, then registries
+pub fn template_sources() -> Vec<TemplateSource> {
+    let mut sources = vec![TemplateSource {
+        name: "system".to_string(),
+        title: format!("📦 System templates ({}):", SYSTEM_TEMPLATES_DIR),
+        location: SourceLocation::Dir(PathBuf::from(SYSTEM_TEMPLATES_DIR)),
+    }];
+
+    if let Some(home) = dirs::home_dir() {
+        sources.push(TemplateSource {
+            name: "user".to_string(),
+            title: "👤 User templates (~/.genj):".to_string(),
+            location: SourceLocation::Dir(home.join(".genj")),
+        });
+    }
+
+    if let Some(custom) = std::env::var_os(TEMPLATE_PATH_ENV) {
+        for dir in std::env::split_paths(&custom).filter(|d| !d.as_os_str().is_empty()) {
+            sources.push(TemplateSource {
+                name: dir.display().to_string(),
+                title: format!("📁 Custom templates ({}):", dir.display()),
+                location: SourceLocation::Dir(dir),
+            });
+        }
+    }
+
+    for url in registry_urls() {
+        sources.push(TemplateSource {
+            name: url.clone(),
+            title: format!("🌐 Registry templates ({}):", url),
+            location: SourceLocation::Registry(url),
+        });
+    }
+
+    sources
+}
+
+/// Templates (archives and folders, or registry entries) of a source, sorted by name
+pub fn scan_templates(source: &TemplateSource, index: &TemplateIndex) -> Vec<TemplateEntry> {
+    let dir = match &source.location {
+        SourceLocation::Dir(dir) => dir,
+        SourceLocation::Registry(url) => return scan_registry(source, url),
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    let mut templates: Vec<TemplateEntry> = paths
+        .into_par_iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+            let (kind, metadata) = index.template(&path)?;
+            Some(TemplateEntry { name, source: source.name.clone(), kind, path, url: None, metadata })
+        })
+        .collect();
+
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+/// Templates of a registry, named `registry:<name>@<version>` so they can be passed to `--template`
+fn scan_registry(source: &TemplateSource, url: &str) -> Vec<TemplateEntry> {
+    let templates = match fetch_index(url) {
+        Ok(templates) => templates,
+        Err(e) => {
+            log_warning(&format!("Unable to read registry {}: {}", url, e));
+            return Vec::new();
+        }
+    };
+
+    let mut entries: Vec<TemplateEntry> = templates
+        .into_iter()
+        .map(|t| TemplateEntry {
+            name: format!("{}{}@{}", REGISTRY_PREFIX, t.name, t.version),
+            source: source.name.clone(),
+            kind: TemplateKind::Registry,
+            path: t.cache_path(),
+            url: Some(t.url),
+            metadata: Some(t.metadata),
+        })
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+}
+
+/// Scan every source in parallel through the template index, keeping the source order
+fn scan_sources(sources: Vec<TemplateSource>, index: &TemplateIndex) -> Vec<(TemplateSource, Vec<TemplateEntry>)> {
+    let scanned: Vec<Vec<TemplateEntry>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = sources
+            .iter()
+            .map(|source| scope.spawn(move || scan_templates(source, index)))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap_or_default()).collect()
+    });
+
+    let dirs: Vec<PathBuf> = sources.iter().filter_map(|s| s.dir()).map(Path::to_path_buf).collect();
+    index.save_or_warn(&dirs);
+
+    sources.into_iter().zip(scanned).collect()
+}
+
+/// Rebuild the template index from scratch. Returns the number of indexed templates.
+pub fn reindex_templates() -> io::Result<usize> {
+    let index = TemplateIndex::empty();
+    let sources: Vec<TemplateSource> = template_sources().into_iter().filter(|s| s.dir().is_some()).collect();
+    let dirs: Vec<PathBuf> = sources.iter().filter_map(|s| s.dir()).map(Path::to_path_buf).collect();
+    let found = scan_sources(sources, &index);
+    index.save(&dirs)?;
+
+    let count = found.iter().map(|(_, t)| t.len()).sum();
+    match index.file() {
+        Some(file) => log_success(&format!("Indexed {} templates into {}", count, file.display())),
+        None => log_success(&format!("Indexed {} templates", count)),
+    }
+    Ok(count)
+}
+
+/// List available templates from system and user directories with metadata
+pub fn list_available_templates(format: OutputFormat) -> io::Result<()> {
+    let found = scan_sources(template_sources(), &TemplateIndex::load());
+
+    match format {
+        OutputFormat::Json => return piped(print_json(found.iter().flat_map(|(_, t)| t).map(|t| (t, None)))),
+        OutputFormat::Plain => return piped(print_plain(found.iter().flat_map(|(_, t)| t).map(|t| (t, None)))),
+        OutputFormat::Table => {
+            let bold = use_colors();
+            println!("=== Available Templates ===\n");
+            for (i, (source, templates)) in found.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!("{}", source.title);
+                if !source.is_available() {
+                    println!("  (No templates found - directory does not exist)");
+                } else if templates.is_empty() {
+                    println!("  (No templates found)");
+                } else {
+                    for template in templates {
+                        print_template_details(template, bold, None);
+                    }
+                    println!();
+                }
+            }
+            print_usage();
+        }
+    }
+    Ok(())
+}
+
+/// Search for templates with a query (see [`Query`]), best matches first
+pub fn search_templates(search_term: &str, format: OutputFormat) -> io::Result<()> {
+    let query = Query::parse(search_term);
+    let sources = template_sources().into_iter().filter(TemplateSource::is_available).collect();
+    let mut hits: Vec<(TemplateEntry, SearchHit)> = scan_sources(sources, &TemplateIndex::load())
+        .into_iter()
+        .flat_map(|(_, templates)| templates)
+        .filter_map(|t| {
+            let hit = query.evaluate(&t.name, t.metadata.as_ref())?;
+            Some((t, hit))
+        })
+        .collect();
+    // stable sort: equally relevant templates keep the source order, then the name order
+    hits.sort_by_key(|(_, hit)| std::cmp::Reverse(hit.score));
+
+    match format {
+        OutputFormat::Json => return piped(print_json(hits.iter().map(|(t, h)| (t, Some(h))))),
+        OutputFormat::Plain => return piped(print_plain(hits.iter().map(|(t, h)| (t, Some(h))))),
+        OutputFormat::Table => {
+            let colors = use_colors();
+            println!("=== Search Results for: '{}' ===", search_term);
+            for (template, hit) in &hits {
+                print_template_details(template, colors, Some(hit));
+            }
+            if hits.is_empty() {
+                println!("\n  No templates found matching '{}'", search_term);
+            }
+            print_usage();
+        }
+    }
+    Ok(())
+}
+
+fn tags(metadata: &Value) -> Vec<&str> {
+    metadata
+        .get("tags")
+        .and_then(|v| v.as_array())
+        .map(|tags| tags.iter().filter_map(|t| t.as_str()).collect())
+        .unwrap_or_default()
+}
+
+fn print_template_details(template: &TemplateEntry, colors: bool, hit: Option<&SearchHit>) {
+    let highlight = |field: Field, value: &str| match hit {
+        Some(hit) if colors => highlight_matches(hit, field, value),
+        _ => value.to_string(),
+    };
+
+    let name = highlight(Field::Name, &template.name);
+    match template.kind {
+        TemplateKind::Archive(_) | TemplateKind::Registry => println!("\n  📋 Template: {}", name),
+        TemplateKind::Dir => println!("\n  📋 Template: {}/", name),
+    }
+    if let Some(hit) = hit {
+        println!("     {} {}", label_text("Source", colors), template.source);
+        println!("     {} {}", label_text("Matched", colors), hit.matched_fields().join(", "));
+    }
+
+    let Some(metadata) = &template.metadata else {
+        println!("     (No metadata available)");
+        return;
+    };
+
+    for (key, label, field) in METADATA_LABELS {
+        if let Some(value) = metadata.get(key).and_then(|v| v.as_str()) {
+            println!("     {} {}", label_text(label, colors), highlight(field, value));
+        }
+    }
+    let tag_strs: Vec<String> = tags(metadata).into_iter().map(|t| highlight(Field::Tag, t)).collect();
+    if !tag_strs.is_empty() {
+        println!("     {} {}", label_text("Tags", colors), tag_strs.join(", "));
+    }
+    if let Some(created_at) = metadata.get("created_at").and_then(|v| v.as_str()) {
+        println!("     {} {}", label_text("Created", colors), created_at);
+    }
+}
+
+/// Wrap the parts of `value` matched by the query in ANSI highlight escapes
+fn highlight_matches(hit: &SearchHit, field: Field, value: &str) -> String {
+    let mut ranges: Vec<(usize, usize)> = hit
+        .matches
+        .iter()
+        .filter(|m| m.field == field && m.value == value && m.start < m.end)
+        .filter(|m| value.is_char_boundary(m.start) && value.is_char_boundary(m.end))
+        .map(|m| (m.start, m.end))
+        .collect();
+    ranges.sort();
+
+    let mut result = String::new();
+    let mut pos = 0;
+    for (start, end) in ranges {
+        if start < pos {
+            continue;
+        }
+        result.push_str(&value[pos..start]);
+        result.push_str(&format!("\x1b[1;33m{}\x1b[0m", &value[start..end]));
+        pos = end;
+    }
+    result.push_str(&value[pos..]);
+    result
+}
+
+fn label_text(label: &str, bold: bool) -> String {
+    if bold {
+        format!("\x1b[1m{}:\x1b[0m", label)
+    } else {
+        format!("{}:", label)
+    }
+}
+
+/// The JSON and plain outputs are meant for pipes: a reader that stops early
+/// (`genj --list --format plain | head -1`) ends the output quietly
+fn piped(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+/// One JSON array with a record per template
+fn print_json<'a>(templates: impl Iterator<Item = (&'a TemplateEntry, Option<&'a SearchHit>)>) -> io::Result<()> {
+    let records: Vec<Value> = templates.map(|(t, hit)| t.to_json(hit)).collect();
+    let mut out = io::stdout().lock();
+    writeln!(out, "{}", serde_json::to_string_pretty(&records).unwrap_or_else(|_| "[]".to_string()))?;
+    out.flush()
+}
+
+/// One tab-separated line per template: name, source, kind, path, version, description,
+/// followed by the score and the matched fields for search results
+fn print_plain<'a>(templates: impl Iterator<Item = (&'a TemplateEntry, Option<&'a SearchHit>)>) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    for (template, hit) in templates {
+        let field = |key: &str| {
+            template
+                .metadata
+                .as_ref()
+                .and_then(|m| m.get(key))
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .replace(['\t', '\n'], " ")
+        };
+        let mut line = format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            template.name,
+            template.source,
+            template.kind.as_str(),
+            absolute(&template.path).display(),
+            field("version"),
+            field("description")
+        );
+        if let Some(hit) = hit {
+            line.push_str(&format!("\t{}\t{}", hit.score, hit.matched_fields().join(",")));
+        }
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()
+}
+
+fn print_usage() {
+    println!("\n💡 Usage: genj -t <template_name_or_path> -d <destination> [options]");
+    println!("   Or: genj -t /usr/share/genj/templates/basic-java.zip -d ./out -n MyProject");
+}
+
+/// ANSI styles are only used when stdout is a terminal
+fn use_colors() -> bool {
+    io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,350 @@
+use crate::cli::{Ci, Cli};
+use crate::log::{log_success, log_verbose};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// How the pipelines and the container image build the project: the template
+/// build script wins over the build tool, as in the VSCode tasks
+pub(crate) struct Build {
+    /// Container image with the JDK and the build tool
+    pub(crate) image: String,
+    command: &'static str,
+    /// Build without the tests, for the container image
+    pub(crate) package: &'static str,
+    /// The template build script is used
+    pub(crate) script: bool,
+    /// Built JAR files, relative to the project
+    jars: &'static str,
+    /// Dependency cache folder inside the project and the variable pointing the
+    /// build tool to it; the build script uses the JARs of `libs/`
+    cache: Option<(&'static str, &'static str)>,
+}
+
+impl Build {
+    pub(crate) fn new(dest: &Path, cli: &Cli) -> Build {
+        if dest.join("build").is_file() {
+            Build {
+                image: format!("eclipse-temurin:{}-jdk", cli.java),
+                command: "./build c b t m j",
+                package: "./build c b m j",
+                script: true,
+                jars: "target/build/*.jar",
+                cache: None,
+            }
+        } else if cli.build_tool.eq_ignore_ascii_case("gradle") {
+            Build {
+                image: format!("gradle:{}-jdk{}", cli.gradle_version, cli.java),
+                command: "gradle build",
+                package: "gradle build -x test",
+                script: false,
+                jars: "build/libs/*.jar",
+                cache: Some((".gradle", "GRADLE_USER_HOME")),
+            }
+        } else {
+            Build {
+                image: format!("maven:{}-eclipse-temurin-{}", cli.maven_version, cli.java),
+                command: "mvn -B verify",
+                package: "mvn -B package -DskipTests",
+                script: false,
+                jars: "target/*.jar",
+                cache: Some((".m2/repository", "MAVEN_OPTS")),
+            }
+        }
+    }
+
+    /// Value of the cache variable for a project checked out in `dir`
+    fn cache_variable(&self, dir: &str) -> Option<(&'static str, String)> {
+        self.cache.map(|(path, variable)| match variable {
+            "MAVEN_OPTS" => (variable, format!("-Dmaven.repo.local={}/{}", dir, path)),
+            _ => (variable, format!("{}/{}", dir, path)),
+        })
+    }
+}
+
+/// Write the pipelines selected with `--ci`, unless the template provides them
+pub fn write_ci(dest: &Path, cli: &Cli) -> io::Result<()> {
+    let build = Build::new(dest, cli);
+    for ci in &cli.ci {
+        let (path, content) = match ci {
+            Ci::Github => (".github/workflows/build.yml", github(&build, cli)),
+            Ci::Gitlab => (".gitlab-ci.yml", gitlab(&build)),
+            Ci::Jenkins => ("Jenkinsfile", jenkins(&build)),
+            Ci::Woodpecker => (".woodpecker.yaml", woodpecker(&build)),
+        };
+        let file = dest.join(path);
+        if file.exists() {
+            log_verbose(&format!("{} provided by the template, not generated", path), cli.verbose);
+            continue;
+        }
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&file, content)?;
+        log_success(&format!("{} generated", path));
+    }
+    Ok(())
+}
+
+/// `actions/setup-java` distribution of a SDKMAN identifier such as `21.0.2-tem`
+fn setup_java_distribution(flavor: &str) -> &'static str {
+    match flavor.rsplit('-').next().unwrap_or_default() {
+        "zulu" => "zulu",
+        "librca" => "liberica",
+        "ms" => "microsoft",
+        "amzn" => "corretto",
+        "sem" => "semeru",
+        "oracle" => "oracle",
+        "graal" | "graalce" => "graalvm",
+        "sapmchn" => "sapmachine",
+        "jbr" => "jetbrains",
+        _ => "temurin",
+    }
+}
+
+fn github(build: &Build, cli: &Cli) -> String {
+    let distribution = setup_java_distribution(&cli.java_flavor);
+    let setup = match build.cache {
+        Some((_, "MAVEN_OPTS")) => format!(
+            r#"      - uses: actions/setup-java@v4
+        with:
+          distribution: {distribution}
+          java-version: '{java}'
+          cache: maven
+"#,
+            distribution = distribution,
+            java = cli.java,
+        ),
+        Some(_) => format!(
+            r#"      - uses: actions/setup-java@v4
+        with:
+          distribution: {distribution}
+          java-version: '{java}'
+      - uses: gradle/actions/setup-gradle@v4
+        with:
+          gradle-version: '{gradle}'
+"#,
+            distribution = distribution,
+            java = cli.java,
+            gradle = cli.gradle_version,
+        ),
+        None => format!(
+            r#"      - uses: actions/setup-java@v4
+        with:
+          distribution: {distribution}
+          java-version: '{java}'
+"#,
+            distribution = distribution,
+            java = cli.java,
+        ),
+    };
+    format!(
+        r#"name: Build
+
+on:
+  push:
+  pull_request:
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+{setup}      - name: Build
+        run: {command}
+      - uses: actions/upload-artifact@v4
+        with:
+          name: {name}
+          path: {jars}
+"#,
+        setup = setup,
+        command = build.command,
+        name = cli.project_name,
+        jars = build.jars,
+    )
+}
+
+fn gitlab(build: &Build) -> String {
+    let mut pipeline = format!("image: {}\n\n", build.image);
+    if let (Some((path, _)), Some((variable, value))) = (build.cache, build.cache_variable("$CI_PROJECT_DIR")) {
+        pipeline.push_str(&format!(
+            "variables:\n  {}: \"{}\"\n\ncache:\n  key: \"$CI_JOB_NAME\"\n  paths:\n    - {}/\n\n",
+            variable, value, path
+        ));
+    }
+    pipeline.push_str(&format!(
+        r#"build:
+  stage: build
+  script:
+    - {command}
+  artifacts:
+    paths:
+      - {jars}
+"#,
+        command = build.command,
+        jars = build.jars,
+    ));
+    pipeline
+}
+
+fn jenkins(build: &Build) -> String {
+    // the cache lives in the agent workspace, kept between builds of the job
+    let environment = match build.cache_variable("${WORKSPACE}") {
+        Some((variable, value)) => format!("    environment {{\n        {} = \"{}\"\n    }}\n", variable, value),
+        None => String::new(),
+    };
+    format!(
+        r#"pipeline {{
+    agent {{
+        docker {{
+            image '{image}'
+        }}
+    }}
+{environment}    stages {{
+        stage('Build') {{
+            steps {{
+                sh '{command}'
+            }}
+        }}
+    }}
+    post {{
+        success {{
+            archiveArtifacts artifacts: '{jars}', fingerprint: true
+        }}
+    }}
+}}
+"#,
+        image = build.image,
+        environment = environment,
+        command = build.command,
+        jars = build.jars,
+    )
+}
+
+fn woodpecker(build: &Build) -> String {
+    // Woodpecker has no cache of its own: a named volume keeps the dependencies
+    // (trusted repositories only), and tagged builds attach the JAR to the release
+    let cache = match build.cache_variable("/cache") {
+        Some((variable, value)) => {
+            format!("    environment:\n      {}: \"{}\"\n    volumes:\n      - genj-cache:/cache\n", variable, value)
+        }
+        None => String::new(),
+    };
+    format!(
+        r#"when:
+  - event: [push, pull_request, tag]
+
+steps:
+  - name: build
+    image: {image}
+{cache}    commands:
+      - {command}
+
+  - name: release
+    image: woodpeckerci/plugin-release
+    settings:
+      files:
+        - {jars}
+      api_key:
+        from_secret: release_token
+    when:
+      - event: tag
+"#,
+        image = build.image,
+        cache = cache,
+        command = build.command,
+        jars = build.jars,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    const ALL: &str = "github,gitlab,jenkins,woodpecker";
+
+    /// Generate every pipeline in a project holding `files`
+    fn generate(args: &[&str], files: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in files {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "from the template").unwrap();
+        }
+        let args = [&["genj", "--project_name", "Demo", "-j", "21", "-f", "21.0.2-tem", "--ci", ALL], args].concat();
+        write_ci(dir.path(), &Cli::try_parse_from(args).unwrap()).unwrap();
+        dir
+    }
+
+    fn read(dir: &tempfile::TempDir, file: &str) -> String {
+        fs::read_to_string(dir.path().join(file)).unwrap()
+    }
+
+    #[test]
+    fn maven_pipelines() {
+        let dir = generate(&[], &[]);
+        let github = read(&dir, ".github/workflows/build.yml");
+        assert!(github.contains("distribution: temurin\n          java-version: '21'\n          cache: maven\n"), "{}", github);
+        assert!(github.contains("run: mvn -B verify\n"), "{}", github);
+        assert!(github.contains("name: Demo\n          path: target/*.jar\n"), "{}", github);
+
+        let gitlab = read(&dir, ".gitlab-ci.yml");
+        assert!(gitlab.starts_with("image: maven:3.9.5-eclipse-temurin-21\n"), "{}", gitlab);
+        assert!(gitlab.contains("  MAVEN_OPTS: \"-Dmaven.repo.local=$CI_PROJECT_DIR/.m2/repository\"\n"), "{}", gitlab);
+        assert!(gitlab.contains("    - .m2/repository/\n"), "{}", gitlab);
+
+        let jenkins = read(&dir, "Jenkinsfile");
+        assert!(jenkins.contains("MAVEN_OPTS = \"-Dmaven.repo.local=${WORKSPACE}/.m2/repository\""), "{}", jenkins);
+        assert!(jenkins.contains("sh 'mvn -B verify'"), "{}", jenkins);
+        assert!(jenkins.contains("archiveArtifacts artifacts: 'target/*.jar'"), "{}", jenkins);
+
+        let woodpecker = read(&dir, ".woodpecker.yaml");
+        assert!(woodpecker.contains("MAVEN_OPTS: \"-Dmaven.repo.local=/cache/.m2/repository\""), "{}", woodpecker);
+        assert!(woodpecker.contains("      - genj-cache:/cache\n"), "{}", woodpecker);
+    }
+
+    #[test]
+    fn gradle_pipelines() {
+        let dir = generate(&["-b", "gradle"], &[]);
+        let github = read(&dir, ".github/workflows/build.yml");
+        assert!(github.contains("uses: gradle/actions/setup-gradle@v4\n        with:\n          gradle-version: '8.5'\n"), "{}", github);
+        assert!(!github.contains("cache: maven"), "{}", github);
+        assert!(github.contains("run: gradle build\n"), "{}", github);
+
+        let gitlab = read(&dir, ".gitlab-ci.yml");
+        assert!(gitlab.starts_with("image: gradle:8.5-jdk21\n"), "{}", gitlab);
+        assert!(gitlab.contains("  GRADLE_USER_HOME: \"$CI_PROJECT_DIR/.gradle\"\n"), "{}", gitlab);
+        assert!(gitlab.contains("      - build/libs/*.jar\n"), "{}", gitlab);
+        assert!(read(&dir, "Jenkinsfile").contains("GRADLE_USER_HOME = \"${WORKSPACE}/.gradle\""));
+        assert!(read(&dir, ".woodpecker.yaml").contains("        - build/libs/*.jar\n"));
+    }
+
+    #[test]
+    fn build_script_pipelines_need_no_cache() {
+        let dir = generate(&["-b", "gradle"], &["build"]);
+        let github = read(&dir, ".github/workflows/build.yml");
+        assert!(github.contains("run: ./build c b t m j\n"), "{}", github);
+        assert!(!github.contains("setup-gradle"), "{}", github);
+        assert!(github.contains("path: target/build/*.jar\n"), "{}", github);
+
+        let gitlab = read(&dir, ".gitlab-ci.yml");
+        assert!(gitlab.starts_with("image: eclipse-temurin:21-jdk\n\nbuild:\n"), "{}", gitlab);
+        let jenkins = read(&dir, "Jenkinsfile");
+        assert!(!jenkins.contains("environment"), "{}", jenkins);
+        assert!(jenkins.contains("sh './build c b t m j'"), "{}", jenkins);
+        let woodpecker = read(&dir, ".woodpecker.yaml");
+        assert!(!woodpecker.contains("volumes"), "{}", woodpecker);
+        assert!(woodpecker.contains("      - ./build c b t m j\n"), "{}", woodpecker);
+    }
+
+    #[test]
+    fn pipelines_of_the_template_are_kept() {
+        let dir = generate(&[], &["Jenkinsfile"]);
+        assert_eq!(read(&dir, "Jenkinsfile"), "from the template");
+        assert!(dir.path().join(".gitlab-ci.yml").is_file());
+        assert_eq!(setup_java_distribution("21.0.2-zulu"), "zulu");
+        assert_eq!(setup_java_distribution("21.0.2-librca"), "liberica");
+        assert_eq!(setup_java_distribution("21"), "temurin");
+    }
+}
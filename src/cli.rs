@@ -1,4 +1,6 @@
-use clap::Parser;
+use crate::forge::Forge;
+use crate::license::License;
+use clap::{Parser, Subcommand, ValueEnum};
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -44,14 +46,138 @@ pub struct Cli {
     pub gradle_version: String,
     #[arg(short = 'l', long = "vendor_name", default_value = "Vendor")]
     pub vendor_name: String,
+    #[arg(long = "license", help = "License of the project: writes LICENSE and the <licenses> block of the POM", value_enum)]
+    pub license: Option<License>,
+    #[arg(long = "license-header", help = "Prepend an SPDX license header to the generated .java files", requires = "license", action = clap::ArgAction::SetTrue)]
+    pub license_header: bool,
+    #[arg(long = "ide", help = "IDE configurations to generate, comma-separated", value_enum, value_delimiter = ',', default_value = "vscode")]
+    pub ide: Vec<Ide>,
+    #[arg(long = "ci", help = "CI pipelines to generate, comma-separated", value_enum, value_delimiter = ',')]
+    pub ci: Vec<Ci>,
+    #[arg(long = "container", help = "Generate a multi-stage Dockerfile, .dockerignore and .devcontainer/devcontainer.json", action = clap::ArgAction::SetTrue)]
+    pub container: bool,
     #[arg(short = 'r', long = "remote_git_repository", help = "Define the remote git repository for this project")]
     pub remote_git: Option<String>,
+    #[arg(long = "push", help = "Push the initial commit to the remote repository (-r)", requires = "remote_git", conflicts_with = "no_git", action = clap::ArgAction::SetTrue)]
+    pub push: bool,
+    #[arg(long = "create-remote", help = "Create the remote repository on a forge, set it as origin and push the initial commit", value_enum, conflicts_with_all = ["remote_git", "no_git"])]
+    pub create_remote: Option<Forge>,
+    #[arg(long = "remote-owner", help = "Organization or group owning the created repository (default: the owner of the token)", requires = "create_remote")]
+    pub remote_owner: Option<String>,
+    #[arg(long = "remote-visibility", help = "Visibility of the created repository", value_enum, default_value = "private")]
+    pub remote_visibility: Visibility,
+    #[arg(long = "forge-url", help = "URL of the forge instance (default: forges.<forge>.url from ~/.config/genj/config.json, else the public instance)", requires = "create_remote")]
+    pub forge_url: Option<String>,
+    #[arg(long = "no-git", help = "Do not create a Git repository", action = clap::ArgAction::SetTrue)]
+    pub no_git: bool,
+    #[arg(long = "branch", help = "Name of the initial branch", default_value = "main")]
+    pub branch: String,
+    #[arg(long = "scaffold-branch", help = "When the destination is inside a Git repository, commit the project on this new branch instead of the current one", conflicts_with = "no_git")]
+    pub scaffold_branch: Option<String>,
+    #[arg(long = "no-commit", help = "Stage the project in the Git repository without committing it", conflicts_with_all = ["no_git", "push", "create_remote"], action = clap::ArgAction::SetTrue)]
+    pub no_commit: bool,
+    #[arg(long = "commit-message", help = "Message of the initial commit, placeholders such as ${PROJECT_NAME} are replaced", default_value = "Create Project ${PROJECT_NAME}")]
+    pub commit_message: String,
+    #[arg(long = "signature-policy", help = "What to do with unsigned or untrusted ZIP templates (default: signature_policy from ~/.config/genj/config.json, else warn)", value_enum)]
+    pub signature_policy: Option<SignaturePolicy>,
+    #[arg(long = "strict", help = "Fail when the template contains placeholders without a value", action = clap::ArgAction::SetTrue)]
+    pub strict: bool,
     #[arg(long = "verbose", help = "Enable verbose output for debugging", action = clap::ArgAction::SetTrue)]
     pub verbose: bool,
     #[arg(long = "list", help = "List available templates in /usr/share/genj/templates and ~/.genj/", action = clap::ArgAction::SetTrue)]
     pub list: bool,
     #[arg(short = 's', long = "search", help = "Search for templates by name or metadata (description, tags, language, author)")]
     pub search: Option<String>,
+    #[arg(long = "format", help = "Output format of --list and --search", value_enum, default_value = "table")]
+    pub format: OutputFormat,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Output format of the discovery commands (--list, --search)
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable listing with all metadata
+    Table,
+    /// JSON array with one record per template
+    Json,
+    /// One tab-separated line per template
+    Plain,
+}
+
+/// IDE configurations written in the generated project (--ide)
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ide {
+    /// .vscode/settings.json and launch.json
+    Vscode,
+    /// IntelliJ IDEA .idea/ folder and module file
+    Idea,
+    /// Eclipse .project, .classpath and .settings/
+    Eclipse,
+    /// No IDE configuration
+    None,
+}
+
+/// CI pipelines written in the generated project (--ci)
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ci {
+    /// GitHub Actions workflow .github/workflows/build.yml
+    Github,
+    /// .gitlab-ci.yml
+    Gitlab,
+    /// Declarative Jenkinsfile
+    Jenkins,
+    /// .woodpecker.yaml
+    Woodpecker,
+}
+
+/// Visibility of a repository created with --create-remote
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Private,
+    /// Visible to every user of the instance (GitLab, GitHub Enterprise)
+    Internal,
+    Public,
+}
+
+/// Handling of ZIP templates without a trusted signature
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignaturePolicy {
+    /// Print a warning and generate the project
+    Warn,
+    /// Refuse to generate the project
+    Require,
+    /// Skip the verification entirely
+    Ignore,
+}
+
+impl SignaturePolicy {
+    /// Parse a policy name as written in the user configuration
+    pub fn parse(value: &str) -> Option<SignaturePolicy> {
+        <Self as ValueEnum>::from_str(value, true).ok()
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Tools for template authors
+    Template {
+        #[command(subcommand)]
+        action: TemplateCommand,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TemplateCommand {
+    /// Run the test cases found in the template's tests/ folder
+    Test {
+        #[arg(help = "Path to the template (ZIP or folder) to test")]
+        path: String,
+        #[arg(long = "bless", help = "Update the snapshot of each test case with the generated output", action = clap::ArgAction::SetTrue)]
+        bless: bool,
+    },
+    /// Rebuild the template index cache (~/.cache/genj/index.json)
+    Reindex,
 }
 
 impl Cli {
@@ -0,0 +1,60 @@
+use crate::log::log_warning;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+/// Per-user settings read from `~/.config/genj/config.json`.
+///
+/// ```json
+/// { "registries": ["https://templates.example.org/index.json"] }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UserConfig {
+    values: Value,
+}
+
+impl UserConfig {
+    /// Load the user configuration. A missing file gives an empty configuration,
+    /// an invalid one is reported and ignored.
+    pub fn load() -> UserConfig {
+        let Some(path) = config_file() else {
+            return UserConfig::default();
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            return UserConfig::default();
+        };
+        match serde_json::from_str::<Value>(&content) {
+            Ok(values) => UserConfig { values },
+            Err(e) => {
+                log_warning(&format!("Ignoring invalid configuration {}: {}", path.display(), e));
+                UserConfig::default()
+            }
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(|v| v.as_str())
+    }
+
+    /// A list of strings; a single string is accepted as a one-element list
+    pub fn get_strings(&self, key: &str) -> Vec<String> {
+        string_list(self.get(key))
+    }
+}
+
+/// Strings of a JSON array; a single string is accepted as a one-element list
+pub fn string_list(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Array(items)) => items.iter().filter_map(|v| v.as_str().map(str::to_string)).collect(),
+        Some(Value::String(s)) => vec![s.clone()],
+        _ => Vec::new(),
+    }
+}
+
+pub fn config_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("genj").join("config.json"))
+}
@@ -0,0 +1,116 @@
+use crate::ci::Build;
+use crate::cli::Cli;
+use crate::log::{log_success, log_verbose};
+use crate::project_files::{jar_path, main_class};
+use serde_json::json;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// `.dockerignore` entries of every project: VCS and IDE files
+const DOCKER_IGNORED: [&str; 8] = [".git", ".idea", ".vscode", ".devcontainer", "*.iml", ".project", ".classpath", ".settings"];
+
+/// Write `Dockerfile`, `.dockerignore` and `.devcontainer/devcontainer.json`,
+/// unless the template provides them
+pub fn write_container_files(dest: &Path, cli: &Cli) -> io::Result<()> {
+    let build = Build::new(dest, cli);
+    let maven = !cli.build_tool.eq_ignore_ascii_case("gradle");
+    write_unless_exists(dest, "Dockerfile", &dockerfile(&build, cli, maven), cli.verbose)?;
+    write_unless_exists(dest, ".dockerignore", &dockerignore(&build, maven), cli.verbose)?;
+    write_unless_exists(dest, ".devcontainer/devcontainer.json", &devcontainer(&build, cli, maven), cli.verbose)
+}
+
+fn write_unless_exists(dest: &Path, path: &str, content: &str, verbose: bool) -> io::Result<()> {
+    let file = dest.join(path);
+    if file.exists() {
+        log_verbose(&format!("{} provided by the template, not generated", path), verbose);
+        return Ok(());
+    }
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&file, content)?;
+    log_success(&format!("{} generated", path));
+    Ok(())
+}
+
+/// Multi-stage `Dockerfile`: the project is built in the image of the build tool,
+/// then its JAR runs on the JRE of `--java_version` with the POM main class.
+fn dockerfile(build: &Build, cli: &Cli, maven: bool) -> String {
+    let main_class = main_class(cli);
+    // the project folder keeps its name, Gradle names the JAR after it
+    let workdir = format!("/src/{}", cli.project_name);
+    let (cache, copy, classpath) = if build.script {
+        // the build script copies the JARs of libs/ next to the application JAR
+        let jar = format!("{}-{}-{}.jar", cli.project_name, main_class, cli.project_version);
+        (
+            "",
+            format!("COPY --from=build {}/target/build/ ./", workdir),
+            format!("{}:libs/*", jar),
+        )
+    } else {
+        let cache = if maven { "--mount=type=cache,target=/root/.m2 " } else { "--mount=type=cache,target=/home/gradle/.gradle " };
+        (cache, format!("COPY --from=build {}/{} app.jar", workdir, jar_path(cli, maven)), "app.jar".to_string())
+    };
+    format!(
+        r#"# syntax=docker/dockerfile:1
+
+FROM {image} AS build
+WORKDIR {workdir}
+COPY . .
+RUN {cache}{package}
+
+FROM eclipse-temurin:{java}-jre
+WORKDIR /app
+{copy}
+ENTRYPOINT ["java", "-cp", "{classpath}", "{main_class}"]
+"#,
+        image = build.image,
+        workdir = workdir,
+        cache = cache,
+        package = build.package,
+        java = cli.java,
+        copy = copy,
+        classpath = classpath,
+        main_class = main_class,
+    )
+}
+
+/// Keep the build outputs out of the build context; the `build` script of a
+/// template must stay in it
+fn dockerignore(build: &Build, maven: bool) -> String {
+    let outputs: &[&str] = if build.script || maven { &["target"] } else { &["build", ".gradle"] };
+    let mut lines: Vec<&str> = outputs.to_vec();
+    lines.extend(DOCKER_IGNORED);
+    lines.join("\n") + "\n"
+}
+
+/// Dev container with the JDK of `--java_flavor` (SDKMAN vendor such as `tem`
+/// or `zulu`), the build tool and the Java extensions of VSCode
+fn devcontainer(build: &Build, cli: &Cli, maven: bool) -> String {
+    let mut java = json!({ "version": cli.java });
+    if let Some((_, vendor)) = cli.java_flavor.rsplit_once('-') {
+        java["jdkDistro"] = json!(vendor);
+    }
+    if !build.script && maven {
+        java["installMaven"] = json!("true");
+        java["mavenVersion"] = json!(cli.maven_version);
+    } else if !build.script {
+        java["installGradle"] = json!("true");
+        java["gradleVersion"] = json!(cli.gradle_version);
+    }
+    let build_extension = if maven { "vscjava.vscode-maven" } else { "vscjava.vscode-gradle" };
+    let devcontainer = json!({
+        "name": cli.project_name,
+        "image": "mcr.microsoft.com/devcontainers/base:ubuntu",
+        "features": {
+            "ghcr.io/devcontainers/features/java:1": java
+        },
+        "customizations": {
+            "vscode": {
+                "extensions": ["vscjava.vscode-java-pack", "redhat.java", build_extension]
+            }
+        }
+    });
+    serde_json::to_string_pretty(&devcontainer).unwrap_or_default() + "\n"
+}
@@ -0,0 +1,351 @@
+use crate::cli::{Cli, Visibility};
+use crate::config::UserConfig;
+use crate::log::{log_success, log_verbose, log_warning};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use std::io;
+
+/// Environment variable holding the API token of any forge
+pub const FORGE_TOKEN_ENV: &str = "GENJ_FORGE_TOKEN";
+
+/// Forges on which `--create-remote` creates the project repository
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forge {
+    Gitea,
+    Gitlab,
+    Github,
+}
+
+impl Forge {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Forge::Gitea => "gitea",
+            Forge::Gitlab => "gitlab",
+            Forge::Github => "github",
+        }
+    }
+
+    /// Public instance used when no URL is configured
+    fn default_url(&self) -> &'static str {
+        match self {
+            Forge::Gitea => "https://gitea.com",
+            Forge::Gitlab => "https://gitlab.com",
+            Forge::Github => "https://api.github.com",
+        }
+    }
+
+    /// REST API root of an instance given by its base URL
+    fn api_url(&self, url: &str) -> String {
+        let url = url.trim_end_matches('/');
+        let host = url.split_once("://").map_or(url, |(_, rest)| rest);
+        match self {
+            _ if url.contains("/api/") => url.to_string(),
+            Forge::Gitea => format!("{}/api/v1", url),
+            Forge::Gitlab => format!("{}/api/v4", url),
+            Forge::Github if host == "github.com" => self.default_url().to_string(),
+            Forge::Github if host == "api.github.com" => url.to_string(),
+            // GitHub Enterprise Server
+            Forge::Github => format!("{}/api/v3", url),
+        }
+    }
+
+    /// GitHub only creates internal repositories in an organization
+    pub fn check_visibility(&self, owner: Option<&str>, visibility: Visibility) -> io::Result<()> {
+        if *self == Forge::Github && owner.is_none() && visibility == Visibility::Internal {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "GitHub has no internal repositories outside an organization: add --remote-owner <organization>",
+            ));
+        }
+        Ok(())
+    }
+
+    fn token_env(&self) -> &'static str {
+        match self {
+            Forge::Gitea => "GITEA_TOKEN",
+            Forge::Gitlab => "GITLAB_TOKEN",
+            Forge::Github => "GITHUB_TOKEN",
+        }
+    }
+}
+
+/// A forge account, checked with its token before the project is generated
+pub struct ForgeClient {
+    forge: Forge,
+    api: String,
+    token: String,
+    /// Login of the token owner, also used to push over HTTPS
+    pub username: String,
+}
+
+/// Repository created on a forge
+#[derive(Debug)]
+pub struct ForgeRepository {
+    pub clone_url: String,
+    pub web_url: String,
+}
+
+impl ForgeClient {
+    /// Resolve the API URL (`--forge-url`, else `forges.<forge>.url` of the user
+    /// configuration, else the public instance) and the token (`GENJ_FORGE_TOKEN`,
+    /// the forge variable such as `GITHUB_TOKEN`, else `forges.<forge>.token`),
+    /// then check the token with [`ForgeClient::with_token`].
+    pub fn connect(forge: Forge, cli: &Cli) -> io::Result<ForgeClient> {
+        let config = UserConfig::load();
+        let configured = |key: &str| {
+            config
+                .get("forges")
+                .and_then(|f| f.get(forge.as_str()))
+                .and_then(|f| f.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        let url = cli.forge_url.clone().or_else(|| configured("url")).unwrap_or_else(|| forge.default_url().to_string());
+        let token = std::env::var(FORGE_TOKEN_ENV)
+            .or_else(|_| std::env::var(forge.token_env()))
+            .ok()
+            .or_else(|| configured("token"))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "No {} token: set {} or {}, or forges.{}.token in the configuration",
+                        forge.as_str(),
+                        FORGE_TOKEN_ENV,
+                        forge.token_env(),
+                        forge.as_str()
+                    ),
+                )
+            })?;
+        ForgeClient::with_token(forge, &url, token, cli.verbose)
+    }
+
+    /// Client of the forge instance at `url` (web or API URL), checking `token`
+    /// by reading the current user
+    pub fn with_token(forge: Forge, url: &str, token: String, verbose: bool) -> io::Result<ForgeClient> {
+        let mut client = ForgeClient { forge, api: forge.api_url(url), token, username: String::new() };
+        log_verbose(&format!("Checking {} access at {}", forge.as_str(), client.api), verbose);
+        let user = client.call("GET", "/user", None)?;
+        let login = if forge == Forge::Gitlab { "username" } else { "login" };
+        client.username = user.get(login).and_then(|v| v.as_str()).unwrap_or("git").to_string();
+        Ok(client)
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Create an empty repository `name`, for `owner` (organization or group)
+    /// or for the token owner
+    pub fn create_repository(
+        &self,
+        name: &str,
+        description: &str,
+        owner: Option<&str>,
+        visibility: Visibility,
+    ) -> io::Result<ForgeRepository> {
+        self.forge.check_visibility(owner, visibility)?;
+        let private = visibility != Visibility::Public;
+        let created = match self.forge {
+            Forge::Github => {
+                let mut body = json!({ "name": name, "description": description, "private": private });
+                if visibility == Visibility::Internal {
+                    body["visibility"] = json!("internal");
+                }
+                let path = owner.map_or("/user/repos".to_string(), |o| format!("/orgs/{}/repos", encode(o)));
+                self.call("POST", &path, Some(body))?
+            }
+            Forge::Gitea => {
+                if visibility == Visibility::Internal {
+                    log_warning("Gitea has no internal visibility, the repository is private");
+                }
+                let body = json!({ "name": name, "description": description, "private": private });
+                let path = owner.map_or("/user/repos".to_string(), |o| format!("/orgs/{}/repos", encode(o)));
+                self.call("POST", &path, Some(body))?
+            }
+            Forge::Gitlab => {
+                let visibility = visibility.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+                let mut body = json!({ "name": name, "path": name, "description": description, "visibility": visibility });
+                if let Some(owner) = owner {
+                    let namespace = self.call("GET", &format!("/namespaces/{}", encode(owner)), None)?;
+                    body["namespace_id"] = namespace.get("id").cloned().unwrap_or(Value::Null);
+                }
+                self.call("POST", "/projects", Some(body))?
+            }
+        };
+
+        let (clone_key, web_key) = match self.forge {
+            Forge::Gitlab => ("http_url_to_repo", "web_url"),
+            Forge::Github | Forge::Gitea => ("clone_url", "html_url"),
+        };
+        let field = |key: &str| created.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let repository = ForgeRepository { clone_url: field(clone_key), web_url: field(web_key) };
+        if repository.clone_url.is_empty() {
+            return Err(io::Error::other(format!("{} did not return the clone URL of {}", self.forge.as_str(), name)));
+        }
+        log_success(&format!("Repository created on {}: {}", self.forge.as_str(), repository.web_url));
+        Ok(repository)
+    }
+
+    /// Call the REST API and return its JSON answer; errors carry the forge message
+    fn call(&self, method: &str, path: &str, body: Option<Value>) -> io::Result<Value> {
+        let url = format!("{}{}", self.api, path);
+        let mut request = ureq::request(method, &url).set("Accept", "application/json");
+        request = match self.forge {
+            Forge::Github => request.set("Authorization", &format!("Bearer {}", self.token)),
+            Forge::Gitea => request.set("Authorization", &format!("token {}", self.token)),
+            Forge::Gitlab => request.set("PRIVATE-TOKEN", &self.token),
+        };
+        let response = match body {
+            Some(body) => request.set("Content-Type", "application/json").send_string(&body.to_string()),
+            None => request.call(),
+        };
+        let response = response.map_err(|e| match e {
+            ureq::Error::Status(code, response) => {
+                let text = response.into_string().unwrap_or_default();
+                let message = serde_json::from_str::<Value>(&text)
+                    .ok()
+                    .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(str::to_string))
+                    .unwrap_or(text);
+                io::Error::other(format!("{} {} failed ({}): {}", method, url, code, message))
+            }
+            e => io::Error::other(format!("{} {} failed: {}", method, url, e)),
+        })?;
+        let text = response.into_string()?;
+        serde_json::from_str(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid answer from {}: {}", url, e)))
+    }
+}
+
+/// Percent-encode a path segment (GitLab namespaces contain `/`)
+fn encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::HttpStub;
+
+    const TOKEN: &str = "secret-token";
+
+    fn connect(forge: Forge, stub: &HttpStub) -> io::Result<ForgeClient> {
+        ForgeClient::with_token(forge, &stub.url, TOKEN.to_string(), false)
+    }
+
+    fn json(value: Value) -> Vec<u8> {
+        value.to_string().into_bytes()
+    }
+
+    #[test]
+    fn api_url_of_each_instance() {
+        assert_eq!(Forge::Github.api_url("https://github.com/"), "https://api.github.com");
+        assert_eq!(Forge::Github.api_url("https://api.github.com"), "https://api.github.com");
+        assert_eq!(Forge::Github.api_url("https://ghe.example.com"), "https://ghe.example.com/api/v3");
+        assert_eq!(Forge::Gitea.api_url("https://git.example.com"), "https://git.example.com/api/v1");
+        assert_eq!(Forge::Gitlab.api_url("https://gitlab.example.com/"), "https://gitlab.example.com/api/v4");
+        assert_eq!(Forge::Gitlab.api_url("https://example.com/gitlab/api/v4"), "https://example.com/gitlab/api/v4");
+    }
+
+    #[test]
+    fn github_enterprise_repository_is_created() {
+        let stub = HttpStub::start(vec![
+            ("GET", "/api/v3/user".to_string(), 200, json(json!({ "login": "octo" }))),
+            (
+                "POST",
+                "/api/v3/orgs/acme/repos".to_string(),
+                201,
+                json(json!({ "clone_url": "https://ghe/acme/demo.git", "html_url": "https://ghe/acme/demo" })),
+            ),
+            ("POST", "/api/v3/user/repos".to_string(), 422, json(json!({ "message": "name already exists on this account" }))),
+        ]);
+        let client = connect(Forge::Github, &stub).unwrap();
+        assert_eq!(client.username, "octo");
+
+        let repository = client.create_repository("demo", "A demo", Some("acme"), Visibility::Internal).unwrap();
+        assert_eq!(repository.clone_url, "https://ghe/acme/demo.git");
+        let error = client.create_repository("demo", "A demo", None, Visibility::Public).unwrap_err();
+        assert!(error.to_string().contains("(422): name already exists on this account"), "{}", error);
+        let error = client.create_repository("demo", "A demo", None, Visibility::Internal).unwrap_err();
+        assert!(error.to_string().contains("--remote-owner"), "{}", error);
+
+        let requests = stub.requests();
+        assert!(requests.iter().all(|r| r.header("authorization") == Some("Bearer secret-token")));
+        let body: Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(body, json!({ "name": "demo", "description": "A demo", "private": true, "visibility": "internal" }));
+    }
+
+    #[test]
+    fn gitea_repository_is_created() {
+        let stub = HttpStub::start(vec![
+            ("GET", "/api/v1/user".to_string(), 200, json(json!({ "login": "tea" }))),
+            (
+                "POST",
+                "/api/v1/user/repos".to_string(),
+                201,
+                json(json!({ "clone_url": "https://tea/tea/demo.git", "html_url": "https://tea/tea/demo" })),
+            ),
+            ("POST", "/api/v1/orgs/acme/repos".to_string(), 409, json(json!({ "message": "The repository already exists." }))),
+        ]);
+        let client = connect(Forge::Gitea, &stub).unwrap();
+        assert_eq!(client.username, "tea");
+
+        let repository = client.create_repository("demo", "", None, Visibility::Private).unwrap();
+        assert_eq!(repository.web_url, "https://tea/tea/demo");
+        let error = client.create_repository("demo", "", Some("acme"), Visibility::Private).unwrap_err();
+        assert!(error.to_string().contains("(409): The repository already exists."), "{}", error);
+        assert!(stub.requests().iter().all(|r| r.header("authorization") == Some("token secret-token")));
+    }
+
+    #[test]
+    fn gitlab_project_is_created_in_a_group() {
+        let stub = HttpStub::start(vec![
+            ("GET", "/api/v4/user".to_string(), 200, json(json!({ "username": "lab", "login": "ignored" }))),
+            ("GET", "/api/v4/namespaces/acme%2Fjava".to_string(), 200, json(json!({ "id": 42 }))),
+            (
+                "POST",
+                "/api/v4/projects".to_string(),
+                201,
+                json(json!({ "http_url_to_repo": "https://lab/acme/java/demo.git", "web_url": "https://lab/acme/java/demo" })),
+            ),
+        ]);
+        let client = connect(Forge::Gitlab, &stub).unwrap();
+        assert_eq!(client.username, "lab");
+
+        let repository = client.create_repository("demo", "", Some("acme/java"), Visibility::Internal).unwrap();
+        assert_eq!(repository.clone_url, "https://lab/acme/java/demo.git");
+        let requests = stub.requests();
+        assert!(requests.iter().all(|r| r.header("private-token") == Some(TOKEN)));
+        let body: Value = serde_json::from_str(&requests[2].body).unwrap();
+        assert_eq!(body["namespace_id"], 42);
+        assert_eq!(body["visibility"], "internal");
+
+        let error = client.create_repository("demo", "", Some("unknown"), Visibility::Private).unwrap_err();
+        assert!(error.to_string().contains("(404): Not Found"), "{}", error);
+    }
+
+    #[test]
+    fn gitlab_error_without_text_message_is_reported_whole() {
+        let stub = HttpStub::start(vec![
+            ("GET", "/api/v4/user".to_string(), 200, json(json!({ "username": "lab" }))),
+            ("POST", "/api/v4/projects".to_string(), 400, json(json!({ "message": { "name": ["has already been taken"] } }))),
+        ]);
+        let client = connect(Forge::Gitlab, &stub).unwrap();
+        let error = client.create_repository("demo", "", None, Visibility::Private).unwrap_err();
+        assert!(error.to_string().contains("has already been taken"), "{}", error);
+    }
+
+    #[test]
+    fn rejected_token_fails_to_connect() {
+        let stub = HttpStub::start(vec![("GET", "/api/v1/user".to_string(), 401, json(json!({ "message": "token is required" })))]);
+        let error = connect(Forge::Gitea, &stub).err().unwrap();
+        assert!(error.to_string().contains("GET"), "{}", error);
+        assert!(error.to_string().contains("(401): token is required"), "{}", error);
+    }
+}
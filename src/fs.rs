@@ -1,9 +1,9 @@
-use std::fs::{File, create_dir_all, write};
+use std::fs::{File, OpenOptions, create_dir_all, write};
 use std::io::{self, Read};
 use std::path::Path;
 
 pub fn is_text_bytes(buf: &[u8]) -> bool {
-    if buf.iter().any(|&b| b == 0) { return false; }
+    if buf.contains(&0) { return false; }
     std::str::from_utf8(buf).is_ok()
 }
 
@@ -25,4 +25,59 @@ pub fn write_bytes(path: &Path, data: &[u8]) -> io::Result<()> {
     create_parent_dir(path)?;
     write(path, data)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Append to a line-based file (`.gitignore`, `.gitattributes`…) the `lines` it
+/// does not contain yet, creating it if needed. Returns whether it was changed.
+pub fn append_missing_lines(path: &Path, lines: &[String]) -> io::Result<bool> {
+    let mut content = std::fs::read_to_string(path).unwrap_or_default();
+    let missing: Vec<&String> = lines.iter().filter(|l| !content.lines().any(|c| c.trim() == l.as_str())).collect();
+    if missing.is_empty() {
+        return Ok(false);
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    for line in missing {
+        content.push_str(line);
+        content.push('\n');
+    }
+    write_bytes(path, content.as_bytes())?;
+    Ok(true)
+}
+
+/// Create (or replace) a file with the given Unix permission bits. The mode
+/// goes through the process umask like any file created by `open(2)`, so a
+/// template can request `0o755` but never grant more than the user allows.
+/// Without a mode, or on other platforms, the default permissions are used.
+pub fn create_file_with_mode(path: &Path, mode: Option<u32>) -> io::Result<File> {
+    create_parent_dir(path)?;
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::OpenOptionsExt;
+        // the mode only applies to new files
+        if path.symlink_metadata().is_ok() {
+            std::fs::remove_file(path)?;
+        }
+        options.mode(mode & 0o777);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    options.open(path)
+}
+
+/// Unix permission bits of a file (`None` on other platforms)
+pub fn file_mode(path: &Path) -> Option<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::metadata(path).ok().map(|m| m.permissions().mode() & 0o777)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
@@ -20,6 +20,7 @@ pub fn write_genrc(dest: &Path, cli: &Cli) -> io::Result<()> {
         "maven_version": cli.maven_version,
         "gradle_version": cli.gradle_version,
         "vendor_name": cli.vendor_name,
+        "license": cli.license.map(|l| l.spdx_id()),
         "template": cli.template,
         "remote_git_repository": cli.remote_git,
         "created_at": Utc::now().to_rfc3339(),
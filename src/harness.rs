@@ -0,0 +1,429 @@
+use crate::cli::Cli;
+use crate::config::string_list;
+use crate::fs::{is_text_path, write_bytes};
+use crate::log::{log_info, log_success, log_verbose, log_warning};
+use crate::run;
+use crate::archive::{for_each_entry, ArchiveFormat, EntryKind};
+use crate::placeholder::placeholder_names;
+use crate::template::{
+    archive_common_prefix, extract_template_metadata, extract_template_metadata_from_dir, TemplateDelimiters,
+    TEMPLATE_TESTS_DIR,
+};
+use clap::Parser;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, create_dir_all, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Name of the file describing a test case inside `tests/<case>/`
+const TEST_CASE_FILE: &str = "test.json";
+/// Golden tree of a test case, compared with (or replaced by) the generated output
+const SNAPSHOT_DIR: &str = "snapshot";
+/// Generated entries that change on every run and are left out of snapshots
+const SNAPSHOT_IGNORED: [&str; 2] = [".git", ".genrc"];
+
+/// A test case read from `tests/<case>/test.json`
+struct TestCase {
+    name: String,
+    dir: PathBuf,
+    spec: Value,
+}
+
+/// Run every test case of a template. Returns `Ok(true)` when all of them pass.
+pub fn test_template(template: &str, bless: bool, verbose: bool) -> io::Result<bool> {
+    let template_path = Path::new(template);
+    if !template_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Template not found: {}", template),
+        ));
+    }
+    if bless && !template_path.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--bless requires a template folder (snapshots cannot be written into an archive)",
+        ));
+    }
+
+    let work_dir = ScratchDir::new("run");
+    let tests_dir = if template_path.is_dir() {
+        template_path.join(TEMPLATE_TESTS_DIR)
+    } else {
+        let extracted = work_dir.path().join(TEMPLATE_TESTS_DIR);
+        extract_archive_tests(template_path, &extracted)?;
+        extracted
+    };
+
+    let cases = read_test_cases(&tests_dir)?;
+    if cases.is_empty() {
+        log_warning(&format!("No test case found in {}", tests_dir.display()));
+        return Ok(true);
+    }
+
+    let metadata = if template_path.is_dir() {
+        extract_template_metadata_from_dir(template_path)
+    } else {
+        extract_template_metadata(template_path)
+    };
+    let delimiters = TemplateDelimiters::new(metadata.as_ref());
+
+    let mut failed = 0;
+    for case in &cases {
+        log_info(&format!("Running test case '{}'", case.name));
+        let failures = run_test_case(template, case, work_dir.path(), &delimiters, bless, verbose)?;
+        if failures.is_empty() {
+            log_success(&format!("Test case '{}' passed", case.name));
+        } else {
+            failed += 1;
+            for failure in &failures {
+                log_warning(&format!("{}: {}", case.name, failure));
+            }
+        }
+    }
+
+    drop(work_dir);
+
+    println!();
+    println!("=== Template Test Summary ===");
+    println!("Passed: {}", cases.len() - failed);
+    println!("Failed: {}", failed);
+
+    Ok(failed == 0)
+}
+
+/// Generate the project described by a test case and check its expectations.
+/// Returns the list of failed expectations.
+fn run_test_case(
+    template: &str,
+    case: &TestCase,
+    work_dir: &Path,
+    delimiters: &TemplateDelimiters,
+    bless: bool,
+    verbose: bool,
+) -> io::Result<Vec<String>> {
+    let dest = work_dir.join(&case.name);
+    if dest.exists() {
+        fs::remove_dir_all(&dest)?;
+    }
+    create_dir_all(&dest)?;
+
+    let cli = build_cli(template, &dest, &case.spec, verbose)?;
+    let output = dest.join(&cli.project_name);
+    run(cli)?;
+
+    let mut failures = Vec::new();
+    let expect = case.spec.get("expect").cloned().unwrap_or(Value::Null);
+
+    for rel in string_list(expect.get("exists")) {
+        if !output.join(&rel).exists() {
+            failures.push(format!("expected '{}' to exist", rel));
+        }
+    }
+
+    for rel in string_list(expect.get("absent")) {
+        if output.join(&rel).exists() {
+            failures.push(format!("expected '{}' to be absent", rel));
+        }
+    }
+
+    if let Some(contains) = expect.get("contains").and_then(|v| v.as_object()) {
+        for (rel, needles) in contains {
+            match read_to_string(output.join(rel)) {
+                Ok(content) => {
+                    for needle in string_list(Some(needles)) {
+                        if !content.contains(&needle) {
+                            failures.push(format!("'{}' does not contain '{}'", rel, needle));
+                        }
+                    }
+                }
+                Err(e) => failures.push(format!("cannot read '{}': {}", rel, e)),
+            }
+        }
+    }
+
+    let check_placeholders = expect
+        .get("no_placeholders")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    if check_placeholders {
+        let allowed = string_list(expect.get("allow_placeholders"));
+        let skipped = string_list(expect.get("skip_placeholders_in"));
+        failures.extend(find_leftover_placeholders(&output, delimiters, &allowed, &skipped)?);
+    }
+
+    let snapshot = case.dir.join(SNAPSHOT_DIR);
+    if bless {
+        bless_snapshot(&output, &snapshot)?;
+        log_success(&format!("Snapshot updated: {}", snapshot.display()));
+    } else if snapshot.is_dir() {
+        failures.extend(compare_with_snapshot(&output, &snapshot)?);
+    } else {
+        log_verbose(&format!("No snapshot for '{}'", case.name), verbose);
+    }
+
+    Ok(failures)
+}
+
+/// Build the `Cli` used to generate a test case: every entry of `variables`
+/// is passed as the matching long option (e.g. `"package": "com.acme"`).
+fn build_cli(template: &str, dest: &Path, spec: &Value, verbose: bool) -> io::Result<Cli> {
+    let mut args = vec![
+        "genj".to_string(),
+        "--template".to_string(),
+        template.to_string(),
+        "--destination".to_string(),
+        dest.display().to_string(),
+    ];
+    if verbose {
+        args.push("--verbose".to_string());
+    }
+
+    if let Some(variables) = spec.get("variables").and_then(|v| v.as_object()) {
+        for (key, value) in variables {
+            if key == "template" || key == "destination" {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("'{}' cannot be set by a test case", key),
+                ));
+            }
+            // flags: `true` passes the option alone, `false` leaves it out
+            let value = match value {
+                Value::Bool(false) | Value::Null => continue,
+                Value::Bool(true) => None,
+                Value::String(s) => Some(s.clone()),
+                other => Some(other.to_string()),
+            };
+            args.push(format!("--{}", key));
+            args.extend(value);
+        }
+    }
+
+    Cli::try_parse_from(args).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Read the test cases from `tests/<case>/test.json`, sorted by name
+fn read_test_cases(tests_dir: &Path) -> io::Result<Vec<TestCase>> {
+    if !tests_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut cases = Vec::new();
+    for entry in fs::read_dir(tests_dir)? {
+        let dir = entry?.path();
+        let spec_file = dir.join(TEST_CASE_FILE);
+        if !spec_file.is_file() {
+            continue;
+        }
+        let name = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let spec = serde_json::from_str::<Value>(&read_to_string(&spec_file)?).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid test case {}: {}", spec_file.display(), e),
+            )
+        })?;
+        cases.push(TestCase { name, dir, spec });
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+/// Copy the `tests/` folder of a template archive into `dest`
+fn extract_archive_tests(archive_path: &Path, dest: &Path) -> io::Result<()> {
+    let format = ArchiveFormat::detect(archive_path)?.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Not a template archive: {}", archive_path.display()))
+    })?;
+    let mut entry_names = Vec::new();
+    for_each_entry(archive_path, format, |entry| {
+        entry_names.push(entry.name);
+        Ok(true)
+    })?;
+    let tests_prefix = format!(
+        "{}{}/",
+        archive_common_prefix(&entry_names).unwrap_or_default(),
+        TEMPLATE_TESTS_DIR
+    );
+
+    for_each_entry(archive_path, format, |entry| {
+        let Some(rel) = entry.name.strip_prefix(&tests_prefix).filter(|_| entry.enclosed) else {
+            return Ok(true);
+        };
+        match entry.kind {
+            EntryKind::Dir => create_dir_all(dest.join(rel))?,
+            EntryKind::File => {
+                let mut bytes = Vec::new();
+                entry.reader.read_to_end(&mut bytes)?;
+                write_bytes(&dest.join(rel), &bytes)?;
+            }
+            _ => {}
+        }
+        Ok(true)
+    })
+}
+
+/// Report every `${UPPER_CASE}` token left in the text files of the output
+fn find_leftover_placeholders(
+    output: &Path,
+    delimiters: &TemplateDelimiters,
+    allowed: &[String],
+    skipped: &[String],
+) -> io::Result<Vec<String>> {
+    let mut failures = Vec::new();
+    for (rel, path) in snapshot_files(output) {
+        if skipped.contains(&rel) || !is_text_path(&path)? {
+            continue;
+        }
+        let content = read_to_string(&path)?;
+        let delimiters = delimiters.for_file(&rel);
+        for (line_no, line) in content.lines().enumerate() {
+            for token in placeholder_names(line, delimiters) {
+                if !allowed.iter().any(|a| a == token) {
+                    failures.push(format!("leftover placeholder {} in {}:{}", delimiters.wrap(token), rel, line_no + 1));
+                }
+            }
+        }
+    }
+    Ok(failures)
+}
+
+/// Files of a generated tree keyed by their `/`-separated relative path,
+/// without the entries that change on every generation.
+fn snapshot_files(root: &Path) -> BTreeMap<String, PathBuf> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| {
+            e.path()
+                .strip_prefix(root)
+                .ok()
+                .and_then(|rel| rel.components().next())
+                .is_none_or(|first| !SNAPSHOT_IGNORED.iter().any(|i| first.as_os_str() == *i))
+        })
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let rel = e.path().strip_prefix(root).ok()?;
+            let key = rel.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+            Some((key, e.path().to_path_buf()))
+        })
+        .collect()
+}
+
+fn compare_with_snapshot(output: &Path, snapshot: &Path) -> io::Result<Vec<String>> {
+    let actual = snapshot_files(output);
+    let expected = snapshot_files(snapshot);
+    let mut failures = Vec::new();
+
+    for (rel, expected_path) in &expected {
+        match actual.get(rel) {
+            None => failures.push(format!("snapshot file '{}' was not generated", rel)),
+            Some(actual_path) => {
+                if fs::read(actual_path)? != fs::read(expected_path)? {
+                    failures.push(format!("'{}' differs from the snapshot", rel));
+                }
+            }
+        }
+    }
+    for rel in actual.keys() {
+        if !expected.contains_key(rel) {
+            failures.push(format!("'{}' is not in the snapshot", rel));
+        }
+    }
+    Ok(failures)
+}
+
+fn bless_snapshot(output: &Path, snapshot: &Path) -> io::Result<()> {
+    if snapshot.exists() {
+        fs::remove_dir_all(snapshot)?;
+    }
+    create_dir_all(snapshot)?;
+    for (rel, path) in snapshot_files(output) {
+        write_bytes(&snapshot.join(rel), &fs::read(path)?)?;
+    }
+    Ok(())
+}
+
+/// Temporary folder of a test run, removed when dropped whatever the outcome
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new(purpose: &str) -> ScratchDir {
+        ScratchDir(std::env::temp_dir().join(format!("genj-test-{}-{}", purpose, std::process::id())))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Test runs share the scratch folder of the process
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    /// A folder template with `@@` delimiters and the given test cases
+    fn template(file: &str, cases: &[(&str, &str)]) -> tempfile::TempDir {
+        let template = tempfile::tempdir().unwrap();
+        fs::write(template.path().join(".template"), r#"{"delimiters": ["@@", "@@"]}"#).unwrap();
+        fs::write(template.path().join("hello.txt"), file).unwrap();
+        for (name, spec) in cases {
+            let case = template.path().join(TEMPLATE_TESTS_DIR).join(name);
+            create_dir_all(&case).unwrap();
+            fs::write(case.join(TEST_CASE_FILE), spec).unwrap();
+        }
+        template
+    }
+
+    fn test(template: &tempfile::TempDir) -> io::Result<bool> {
+        test_template(template.path().to_str().unwrap(), false, false)
+    }
+
+    #[test]
+    fn boolean_variables_are_flags() {
+        let spec = |no_git: bool| {
+            serde_json::json!({
+                "variables": { "project_name": "Demo", "no-git": no_git, "verbose": false },
+                "expect": { "exists": ["hello.txt"], "contains": { "hello.txt": ["Hello Demo"] } }
+            })
+            .to_string()
+        };
+        let spec = spec(true);
+        let template = template("Hello @@PROJECT_NAME@@\n", &[("flags", &spec)]);
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        assert!(test(&template).unwrap());
+
+        let spec = serde_json::json!({ "variables": { "no-git": true } });
+        let cli = build_cli("t", Path::new("out"), &spec, false).unwrap();
+        assert!(cli.no_git);
+        let cli = build_cli("t", Path::new("out"), &serde_json::json!({ "variables": { "no-git": false } }), false).unwrap();
+        assert!(!cli.no_git);
+    }
+
+    #[test]
+    fn leftover_placeholders_use_the_template_delimiters() {
+        let case = r#"{"variables": {"project_name": "Demo", "no-git": true}}"#;
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        // `${HOME}` is not a placeholder of this template
+        assert!(test(&template("Hello @@PROJECT_NAME@@ in ${HOME}\n", &[("default", case)])).unwrap());
+        assert!(!test(&template("Hello @@NOT_A_VARIABLE@@\n", &[("default", case)])).unwrap());
+    }
+
+    #[test]
+    fn scratch_folder_is_removed_when_a_test_case_fails_to_run() {
+        let case = r#"{"variables": {"destination": "elsewhere"}}"#;
+        let template = template("", &[("bad", case)]);
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let error = test(&template).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(!ScratchDir::new("run").path().exists());
+    }
+}
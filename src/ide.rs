@@ -0,0 +1,664 @@
+use crate::cli::{Cli, Ide};
+use crate::log::{log_success, log_verbose, log_warning};
+use crate::project_files::main_class;
+use serde_json::{json, Value};
+use std::fs::{create_dir_all, read_dir, read_to_string, write};
+use std::io;
+use std::path::Path;
+
+/// Standard source folders, in the order IDEs list them: (path, test, resources)
+const SOURCE_ROOTS: [(&str, bool, bool); 4] = [
+    ("src/main/java", false, false),
+    ("src/main/resources", false, true),
+    ("src/test/java", true, false),
+    ("src/test/resources", true, true),
+];
+
+/// Layout of the generated project, shared by the IDE configurations
+struct Project<'a> {
+    name: &'a str,
+    main_class: String,
+    java: &'a str,
+    maven: bool,
+    /// Source folders present in the project: (path, test, resources)
+    roots: Vec<(&'static str, bool, bool)>,
+    /// JAR files of `libs/`, relative to the project
+    libs: Vec<String>,
+    /// The template provides a `build` script at the root
+    build_script: bool,
+}
+
+impl Project<'_> {
+    fn output(&self, test: bool) -> &'static str {
+        match (self.maven, test) {
+            (true, false) => "target/classes",
+            (true, true) => "target/test-classes",
+            (false, false) => "build/classes/java/main",
+            (false, true) => "build/classes/java/test",
+        }
+    }
+
+    /// Java version as IDEs spell it: `1.8` for 8, else unchanged
+    fn java_release(&self) -> String {
+        match self.java {
+            "8" => "1.8".to_string(),
+            java => java.to_string(),
+        }
+    }
+}
+
+/// Write the configuration of the IDEs selected with `--ide` (VSCode by default)
+pub fn setup_ides(dest: &Path, cli: &Cli) -> io::Result<()> {
+    if cli.ide.contains(&Ide::None) {
+        log_verbose("No IDE configuration requested", cli.verbose);
+        return Ok(());
+    }
+
+    let mut roots: Vec<_> = SOURCE_ROOTS.into_iter().filter(|(path, _, _)| dest.join(path).is_dir()).collect();
+    if roots.is_empty() {
+        // templates without the Maven layout keep their sources in src/
+        roots.push(("src", false, false));
+    }
+    let mut libs: Vec<String> = match read_dir(dest.join("libs")) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".jar"))
+            .map(|name| format!("libs/{}", name))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    libs.sort();
+
+    let project = Project {
+        name: &cli.project_name,
+        main_class: main_class(cli),
+        java: &cli.java,
+        maven: !cli.build_tool.eq_ignore_ascii_case("gradle"),
+        roots,
+        libs,
+        build_script: dest.join("build").is_file(),
+    };
+
+    for ide in &cli.ide {
+        match ide {
+            Ide::Vscode => write_vscode(dest, &project, cli.verbose)?,
+            Ide::Idea => write_idea(dest, &project, cli.verbose)?,
+            Ide::Eclipse => write_eclipse(dest, &project, cli.verbose)?,
+            Ide::None => {}
+        }
+    }
+    Ok(())
+}
+
+/// `.vscode/` settings, launch configuration, tasks, extension recommendations and
+/// formatter profile. JSON files shipped by the template are deep-merged.
+fn write_vscode(dest: &Path, project: &Project, verbose: bool) -> io::Result<()> {
+    log_verbose("Creating VSCode configuration", verbose);
+    let vscode_dir = dest.join(".vscode");
+    create_dir_all(&vscode_dir)?;
+
+    let mut settings = json!({
+        "java.format.settings.url": ".vscode/java-formatter.xml",
+        "java.project.sourcePaths": project.roots.iter().map(|(path, _, _)| *path).collect::<Vec<_>>(),
+        "java.project.encoding": "warning",
+        "java.project.outputPath": project.output(false)
+    });
+    if !project.libs.is_empty() {
+        settings["java.project.referencedLibraries"] = json!(project.libs);
+    }
+    write_vscode_json(&vscode_dir.join("settings.json"), settings)?;
+
+    let launch = json!({
+        "version": "0.2.0",
+        "configurations": [
+            {
+                "type": "java",
+                "name": "Run",
+                "request": "launch",
+                "mainClass": project.main_class,
+                "projectName": project.name
+            }
+        ]
+    });
+    write_vscode_json(&vscode_dir.join("launch.json"), launch)?;
+
+    // the template build script wins over the build tool, it is what its README documents
+    let (build, test, clean) = if project.build_script {
+        ("./build", "./build t", "./build c")
+    } else if project.maven {
+        ("mvn package", "mvn test", "mvn clean")
+    } else {
+        ("gradle build", "gradle test", "gradle clean")
+    };
+    let tasks = json!({
+        "version": "2.0.0",
+        "tasks": [
+            { "label": "build", "type": "shell", "command": build, "group": { "kind": "build", "isDefault": true }, "problemMatcher": [] },
+            { "label": "test", "type": "shell", "command": test, "group": { "kind": "test", "isDefault": true }, "problemMatcher": [] },
+            { "label": "clean", "type": "shell", "command": clean, "problemMatcher": [] }
+        ]
+    });
+    write_vscode_json(&vscode_dir.join("tasks.json"), tasks)?;
+
+    let build_extension = if project.maven { "vscjava.vscode-maven" } else { "vscjava.vscode-gradle" };
+    let extensions = json!({
+        "recommendations": ["vscjava.vscode-java-pack", "redhat.java", build_extension]
+    });
+    write_vscode_json(&vscode_dir.join("extensions.json"), extensions)?;
+
+    let formatter = vscode_dir.join("java-formatter.xml");
+    if formatter.exists() {
+        log_verbose(".vscode/java-formatter.xml provided by the template, kept", verbose);
+    } else {
+        write(&formatter, include_str!("vscode/java-formatter.xml"))?;
+        log_success(".vscode/java-formatter.xml created");
+    }
+    Ok(())
+}
+
+/// Write a generated `.vscode` JSON file, deep-merged into the template's one if
+/// any: the template values win and generated array items are added when missing.
+/// A template file that cannot be parsed is kept as is.
+fn write_vscode_json(path: &Path, generated: Value) -> io::Result<()> {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let (content, action) = match read_to_string(path) {
+        Ok(existing) => match serde_json::from_str::<Value>(&strip_json_comments(&existing)) {
+            Ok(mut existing) => {
+                merge_json(&mut existing, generated);
+                (existing, "merged")
+            }
+            Err(e) => {
+                log_warning(&format!("Keeping .vscode/{} of the template, it is not valid JSON: {}", name, e));
+                return Ok(());
+            }
+        },
+        Err(_) => (generated, "created"),
+    };
+    write(path, serde_json::to_string_pretty(&content)?)?;
+    log_success(&format!(".vscode/{} {}", name, action));
+    Ok(())
+}
+
+/// Deep-merge `generated` into `base`. Objects are merged key by key, arrays get
+/// the generated items they miss (objects are matched on `name` or `label`), and
+/// any other value of `base` is kept.
+fn merge_json(base: &mut Value, generated: Value) {
+    match (base, generated) {
+        (Value::Object(base), Value::Object(generated)) => {
+            for (key, value) in generated {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(generated)) => {
+            let id = |v: &Value| v.get("name").or_else(|| v.get("label")).cloned();
+            for item in generated {
+                let present = base.iter().any(|b| *b == item || (id(b).is_some() && id(b) == id(&item)));
+                if !present {
+                    base.push(item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// VSCode files are JSON with comments and trailing commas: remove both
+fn strip_json_comments(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut output = String::with_capacity(content.len());
+    // byte offset in `output` of a comma not yet followed by a value
+    let mut comma: Option<usize> = None;
+    let mut i = 0;
+    let mut in_string = false;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            output.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                output.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+        match (c, chars.get(i + 1)) {
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            ('/', Some('*')) => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+                continue;
+            }
+            (c, _) if c.is_whitespace() => output.push(c),
+            (c, _) => {
+                // a trailing comma is followed by the end of the object or array,
+                // possibly after comments
+                if let Some(offset) = comma.take() {
+                    if c == '}' || c == ']' {
+                        output.remove(offset);
+                    }
+                }
+                if c == ',' {
+                    comma = Some(output.len());
+                }
+                in_string = c == '"';
+                output.push(c);
+            }
+        }
+        i += 1;
+    }
+    output
+}
+
+/// `.idea/` (misc.xml, modules.xml, run configuration) and the module file `<name>.iml`
+fn write_idea(dest: &Path, project: &Project, verbose: bool) -> io::Result<()> {
+    log_verbose("Creating IntelliJ IDEA configuration", verbose);
+    let idea_dir = dest.join(".idea");
+    create_dir_all(idea_dir.join("runConfigurations"))?;
+    let name = xml_escape(project.name);
+    let release = project.java_release();
+
+    let build_tool = if project.maven {
+        r#"  <component name="MavenProjectsManager">
+    <option name="originalFiles">
+      <list>
+        <option value="$PROJECT_DIR$/pom.xml" />
+      </list>
+    </option>
+  </component>
+"#
+    } else {
+        r#"  <component name="ExternalStorageConfigurationManager" enabled="true" />
+"#
+    };
+    let misc = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<project version="4">
+{}  <component name="ProjectRootManager" version="2" languageLevel="JDK_{}" default="true" project-jdk-name="{}" project-jdk-type="JavaSDK">
+    <output url="file://$PROJECT_DIR$/out" />
+  </component>
+</project>
+"#,
+        build_tool,
+        release.replace('.', "_"),
+        xml_escape(project.java)
+    );
+    write(idea_dir.join("misc.xml"), misc)?;
+
+    if !project.maven {
+        let gradle = r#"<?xml version="1.0" encoding="UTF-8"?>
+<project version="4">
+  <component name="GradleSettings">
+    <option name="linkedExternalProjectsSettings">
+      <GradleProjectSettings>
+        <option name="externalProjectPath" value="$PROJECT_DIR$" />
+        <option name="modules">
+          <set>
+            <option value="$PROJECT_DIR$" />
+          </set>
+        </option>
+      </GradleProjectSettings>
+    </option>
+  </component>
+</project>
+"#;
+        write(idea_dir.join("gradle.xml"), gradle)?;
+    }
+
+    let modules = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<project version="4">
+  <component name="ProjectModuleManager">
+    <modules>
+      <module fileurl="file://$PROJECT_DIR$/{0}.iml" filepath="$PROJECT_DIR$/{0}.iml" />
+    </modules>
+  </component>
+</project>
+"#,
+        name
+    );
+    write(idea_dir.join("modules.xml"), modules)?;
+
+    let mut folders = String::new();
+    for (path, test, resources) in &project.roots {
+        let kind = match (test, resources) {
+            (false, false) => r#"isTestSource="false""#,
+            (true, false) => r#"isTestSource="true""#,
+            (false, true) => r#"type="java-resource""#,
+            (true, true) => r#"type="java-test-resource""#,
+        };
+        folders.push_str(&format!("      <sourceFolder url=\"file://$MODULE_DIR$/{}\" {} />\n", path, kind));
+    }
+    let excluded = if project.maven { "target" } else { "build" };
+    let mut libraries = String::new();
+    for lib in &project.libs {
+        libraries.push_str(&format!(
+            r#"    <orderEntry type="module-library">
+      <library>
+        <CLASSES>
+          <root url="jar://$MODULE_DIR$/{}!/" />
+        </CLASSES>
+        <JAVADOC />
+        <SOURCES />
+      </library>
+    </orderEntry>
+"#,
+            xml_escape(lib)
+        ));
+    }
+    let iml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<module type="JAVA_MODULE" version="4">
+  <component name="NewModuleRootManager" inherit-compiler-output="false">
+    <output url="file://$MODULE_DIR$/{}" />
+    <output-test url="file://$MODULE_DIR$/{}" />
+    <exclude-output />
+    <content url="file://$MODULE_DIR$">
+{}      <excludeFolder url="file://$MODULE_DIR$/{}" />
+    </content>
+    <orderEntry type="inheritedJdk" />
+    <orderEntry type="sourceFolder" forTests="false" />
+{}  </component>
+</module>
+"#,
+        project.output(false),
+        project.output(true),
+        folders,
+        excluded,
+        libraries
+    );
+    write(dest.join(format!("{}.iml", project.name)), iml)?;
+
+    let main_class = xml_escape(&project.main_class);
+    let simple_name = project.main_class.rsplit('.').next().unwrap_or(&project.main_class);
+    let run = format!(
+        r#"<component name="ProjectRunConfigurationManager">
+  <configuration default="false" name="{}" type="Application" factoryName="Application">
+    <option name="MAIN_CLASS_NAME" value="{}" />
+    <module name="{}" />
+    <method v="2">
+      <option name="Make" enabled="true" />
+    </method>
+  </configuration>
+</component>
+"#,
+        xml_escape(simple_name),
+        main_class,
+        name
+    );
+    write(idea_dir.join("runConfigurations").join(format!("{}.xml", simple_name)), run)?;
+    log_success(&format!(".idea/ and {}.iml created", project.name));
+    Ok(())
+}
+
+/// Eclipse `.project`, `.classpath` and `.settings/`, for m2e (Maven) or Buildship (Gradle)
+fn write_eclipse(dest: &Path, project: &Project, verbose: bool) -> io::Result<()> {
+    log_verbose("Creating Eclipse configuration", verbose);
+    let (builder, nature, container) = if project.maven {
+        ("org.eclipse.m2e.core.maven2Builder", "org.eclipse.m2e.core.maven2Nature", "org.eclipse.m2e.MAVEN2_CLASSPATH_CONTAINER")
+    } else {
+        (
+            "org.eclipse.buildship.core.gradleprojectbuilder",
+            "org.eclipse.buildship.core.gradleprojectnature",
+            "org.eclipse.buildship.core.gradleclasspathcontainer",
+        )
+    };
+    let release = project.java_release();
+
+    let dot_project = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<projectDescription>
+  <name>{}</name>
+  <comment></comment>
+  <projects>
+  </projects>
+  <buildSpec>
+    <buildCommand>
+      <name>org.eclipse.jdt.core.javabuilder</name>
+      <arguments>
+      </arguments>
+    </buildCommand>
+    <buildCommand>
+      <name>{}</name>
+      <arguments>
+      </arguments>
+    </buildCommand>
+  </buildSpec>
+  <natures>
+    <nature>org.eclipse.jdt.core.javanature</nature>
+    <nature>{}</nature>
+  </natures>
+</projectDescription>
+"#,
+        xml_escape(project.name),
+        builder,
+        nature
+    );
+    write(dest.join(".project"), dot_project)?;
+
+    let mut entries = String::new();
+    for (path, test, resources) in &project.roots {
+        // resources are copied by the build tool, not compiled
+        let excluding = if *resources { r#" excluding="**""# } else { "" };
+        let entry = format!(
+            "  <classpathentry kind=\"src\"{} output=\"{}\" path=\"{}\"",
+            excluding,
+            project.output(*test),
+            path
+        );
+        if *test {
+            entries.push_str(&format!(
+                "{}>\n    <attributes>\n      <attribute name=\"test\" value=\"true\"/>\n    </attributes>\n  </classpathentry>\n",
+                entry
+            ));
+        } else {
+            entries.push_str(&format!("{}/>\n", entry));
+        }
+    }
+    for lib in &project.libs {
+        entries.push_str(&format!("  <classpathentry kind=\"lib\" path=\"{}\"/>\n", xml_escape(lib)));
+    }
+    let classpath = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<classpath>
+{}  <classpathentry kind="con" path="org.eclipse.jdt.launching.JRE_CONTAINER/org.eclipse.jdt.internal.debug.ui.launcher.StandardVMType/JavaSE-{}"/>
+  <classpathentry kind="con" path="{}"/>
+  <classpathentry kind="output" path="{}"/>
+</classpath>
+"#,
+        entries,
+        release,
+        container,
+        project.output(false)
+    );
+    write(dest.join(".classpath"), classpath)?;
+
+    let settings = dest.join(".settings");
+    create_dir_all(&settings)?;
+    write(
+        settings.join("org.eclipse.jdt.core.prefs"),
+        format!(
+            "eclipse.preferences.version=1\n\
+             org.eclipse.jdt.core.compiler.codegen.targetPlatform={0}\n\
+             org.eclipse.jdt.core.compiler.compliance={0}\n\
+             org.eclipse.jdt.core.compiler.release=enabled\n\
+             org.eclipse.jdt.core.compiler.source={0}\n",
+            release
+        ),
+    )?;
+    write(
+        settings.join("org.eclipse.core.resources.prefs"),
+        "eclipse.preferences.version=1\nencoding/<project>=UTF-8\n",
+    )?;
+    if project.maven {
+        write(
+            settings.join("org.eclipse.m2e.core.prefs"),
+            "activeProfiles=\neclipse.preferences.version=1\nresolveWorkspaceProjects=true\nversion=1\n",
+        )?;
+    } else {
+        write(
+            settings.join("org.eclipse.buildship.core.prefs"),
+            "connection.project.dir=\neclipse.preferences.version=1\n",
+        )?;
+    }
+    log_success("Eclipse .project, .classpath and .settings/ created");
+    Ok(())
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(maven: bool) -> Project<'static> {
+        Project {
+            name: "Demo",
+            main_class: "com.example.App".to_string(),
+            java: "8",
+            maven,
+            roots: vec![SOURCE_ROOTS[0], SOURCE_ROOTS[1], SOURCE_ROOTS[2]],
+            libs: vec!["libs/a&b.jar".to_string()],
+            build_script: false,
+        }
+    }
+
+    fn read(dir: &Path, file: &str) -> String {
+        read_to_string(dir.join(file)).unwrap()
+    }
+
+    fn parse_jsonc(content: &str) -> Value {
+        serde_json::from_str(&strip_json_comments(content)).unwrap()
+    }
+
+    #[test]
+    fn comments_and_trailing_commas_are_removed() {
+        let content = r#"{
+            // line comment
+            "a": 1, // trailing comma then comment
+            /* block */ "b": [1, 2, /* last */],
+            "url": "http://example.com/*not a comment*/",
+            "quote": "say \"hi\", // still text",
+        }"#;
+        assert_eq!(
+            parse_jsonc(content),
+            json!({ "a": 1, "b": [1, 2], "url": "http://example.com/*not a comment*/", "quote": "say \"hi\", // still text" })
+        );
+        assert_eq!(parse_jsonc("{\"a\": 1, // x\n}"), json!({ "a": 1 }));
+        assert_eq!(parse_jsonc("[\"a,\",\n/* x */\n]"), json!(["a,"]));
+    }
+
+    #[test]
+    fn template_values_win_and_arrays_get_missing_items() {
+        let mut base = json!({
+            "java.project.outputPath": "out",
+            "configurations": [{ "name": "Run", "mainClass": "Custom" }],
+            "recommendations": ["redhat.java"]
+        });
+        let generated = json!({
+            "java.project.outputPath": "target/classes",
+            "java.project.encoding": "warning",
+            "configurations": [{ "name": "Run", "mainClass": "App" }, { "name": "Debug" }],
+            "recommendations": ["vscjava.vscode-java-pack", "redhat.java"]
+        });
+        merge_json(&mut base, generated);
+        assert_eq!(
+            base,
+            json!({
+                "java.project.outputPath": "out",
+                "configurations": [{ "name": "Run", "mainClass": "Custom" }, { "name": "Debug" }],
+                "recommendations": ["redhat.java", "vscjava.vscode-java-pack"],
+                "java.project.encoding": "warning"
+            })
+        );
+    }
+
+    #[test]
+    fn idea_module_lists_sources_libraries_and_run_configuration() {
+        let dir = tempfile::tempdir().unwrap();
+        write_idea(dir.path(), &project(true), false).unwrap();
+
+        let misc = read(dir.path(), ".idea/misc.xml");
+        assert!(misc.contains(r#"languageLevel="JDK_1_8""#), "{}", misc);
+        assert!(misc.contains("$PROJECT_DIR$/pom.xml"), "{}", misc);
+        assert!(!dir.path().join(".idea/gradle.xml").exists());
+        assert!(read(dir.path(), ".idea/modules.xml").contains(r#"filepath="$PROJECT_DIR$/Demo.iml""#));
+
+        let iml = read(dir.path(), "Demo.iml");
+        assert!(iml.contains(r#"<output url="file://$MODULE_DIR$/target/classes" />"#), "{}", iml);
+        assert!(iml.contains(r#"<sourceFolder url="file://$MODULE_DIR$/src/main/java" isTestSource="false" />"#), "{}", iml);
+        assert!(iml.contains(r#"<sourceFolder url="file://$MODULE_DIR$/src/main/resources" type="java-resource" />"#), "{}", iml);
+        assert!(iml.contains(r#"<sourceFolder url="file://$MODULE_DIR$/src/test/java" isTestSource="true" />"#), "{}", iml);
+        assert!(iml.contains(r#"<root url="jar://$MODULE_DIR$/libs/a&amp;b.jar!/" />"#), "{}", iml);
+        assert!(iml.contains(r#"<excludeFolder url="file://$MODULE_DIR$/target" />"#), "{}", iml);
+
+        let run = read(dir.path(), ".idea/runConfigurations/App.xml");
+        assert!(run.contains(r#"<option name="MAIN_CLASS_NAME" value="com.example.App" />"#), "{}", run);
+        assert!(run.contains(r#"<module name="Demo" />"#), "{}", run);
+    }
+
+    #[test]
+    fn idea_gradle_project_is_linked() {
+        let dir = tempfile::tempdir().unwrap();
+        write_idea(dir.path(), &project(false), false).unwrap();
+        assert!(read(dir.path(), ".idea/gradle.xml").contains("GradleProjectSettings"));
+        assert!(read(dir.path(), "Demo.iml").contains("build/classes/java/main"));
+    }
+
+    #[test]
+    fn eclipse_classpath_follows_the_build_tool() {
+        let dir = tempfile::tempdir().unwrap();
+        write_eclipse(dir.path(), &project(true), false).unwrap();
+        assert!(read(dir.path(), ".project").contains("<nature>org.eclipse.m2e.core.maven2Nature</nature>"));
+        let classpath = read(dir.path(), ".classpath");
+        assert!(
+            classpath.contains(r#"<classpathentry kind="src" output="target/classes" path="src/main/java"/>"#),
+            "{}",
+            classpath
+        );
+        assert!(
+            classpath.contains(r#"<classpathentry kind="src" excluding="**" output="target/classes" path="src/main/resources"/>"#),
+            "{}",
+            classpath
+        );
+        assert!(
+            classpath.contains(
+                "<classpathentry kind=\"src\" output=\"target/test-classes\" path=\"src/test/java\">\n    <attributes>\n      <attribute name=\"test\" value=\"true\"/>"
+            ),
+            "{}",
+            classpath
+        );
+        assert!(classpath.contains(r#"<classpathentry kind="lib" path="libs/a&amp;b.jar"/>"#), "{}", classpath);
+        assert!(classpath.contains("StandardVMType/JavaSE-1.8"), "{}", classpath);
+        assert!(classpath.contains("org.eclipse.m2e.MAVEN2_CLASSPATH_CONTAINER"), "{}", classpath);
+        assert!(read(dir.path(), ".settings/org.eclipse.jdt.core.prefs").contains("compiler.release=enabled\n"));
+        assert!(dir.path().join(".settings/org.eclipse.m2e.core.prefs").is_file());
+
+        let dir = tempfile::tempdir().unwrap();
+        write_eclipse(dir.path(), &project(false), false).unwrap();
+        assert!(read(dir.path(), ".project").contains("org.eclipse.buildship.core.gradleprojectnature"));
+        assert!(read(dir.path(), ".classpath").contains(r#"<classpathentry kind="output" path="build/classes/java/main"/>"#));
+        assert!(dir.path().join(".settings/org.eclipse.buildship.core.prefs").is_file());
+    }
+}
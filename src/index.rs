@@ -0,0 +1,233 @@
+use crate::archive::ArchiveFormat;
+use crate::catalog::TemplateKind;
+use crate::log::log_warning;
+use crate::template::{extract_template_metadata, extract_template_metadata_from_dir};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+/// Bumped whenever the layout of `index.json` changes
+const INDEX_VERSION: u64 = 2;
+
+/// Cached kind and `.template` metadata of one entry of a template folder
+#[derive(Debug, Clone)]
+struct IndexEntry {
+    size: u64,
+    mtime: u128,
+    /// `None` for files that are not templates, so that they are not read again
+    kind: Option<TemplateKind>,
+    metadata: Option<Value>,
+}
+
+/// Persistent cache of template metadata, stored in `~/.cache/genj/index.json`.
+///
+/// Entries are keyed by the template path and invalidated when the size or
+/// the modification time of the archive (or of the `.template` file of a
+/// folder template) changes, so only new or modified files are opened.
+pub struct TemplateIndex {
+    file: Option<PathBuf>,
+    state: Mutex<IndexState>,
+}
+
+#[derive(Default)]
+struct IndexState {
+    entries: HashMap<String, IndexEntry>,
+    /// Templates looked up since loading
+    seen: HashSet<String>,
+    dirty: bool,
+}
+
+impl TemplateIndex {
+    /// Load the index from the user cache directory (empty when missing or unreadable)
+    pub fn load() -> TemplateIndex {
+        let file = index_file();
+        let entries = file
+            .as_ref()
+            .and_then(|f| fs::read_to_string(f).ok())
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .filter(|index| index.get("version").and_then(|v| v.as_u64()) == Some(INDEX_VERSION))
+            .map(|index| parse_entries(&index))
+            .unwrap_or_default();
+        TemplateIndex::with_entries(file, entries)
+    }
+
+    /// An empty index written to the user cache directory on save
+    pub fn empty() -> TemplateIndex {
+        TemplateIndex::with_entries(index_file(), HashMap::new())
+    }
+
+    fn with_entries(file: Option<PathBuf>, entries: HashMap<String, IndexEntry>) -> TemplateIndex {
+        TemplateIndex {
+            file,
+            state: Mutex::new(IndexState { entries, ..IndexState::default() }),
+        }
+    }
+
+    /// Kind and `.template` metadata of an entry of a template folder, read from
+    /// the cache when it did not change; `None` when it is not a template.
+    /// Archives are recognized by their content, whatever their extension.
+    pub fn template(&self, path: &Path) -> Option<(TemplateKind, Option<Value>)> {
+        let key = path.display().to_string();
+        let is_dir = path.is_dir();
+        let stamp_file = if is_dir { path.join(".template") } else { path.to_path_buf() };
+        let (size, mtime) = file_stamp(&stamp_file).unwrap_or((0, 0));
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.seen.insert(key.clone());
+            if let Some(entry) = state.entries.get(&key) {
+                let same_type = (entry.kind == Some(TemplateKind::Dir)) == is_dir;
+                if entry.size == size && entry.mtime == mtime && same_type {
+                    return entry.kind.map(|kind| (kind, entry.metadata.clone()));
+                }
+            }
+        }
+
+        // read outside of the lock so that templates are scanned in parallel
+        let kind = if is_dir {
+            Some(TemplateKind::Dir)
+        } else {
+            ArchiveFormat::detect(path).ok().flatten().map(TemplateKind::Archive)
+        };
+        let metadata = match kind {
+            Some(TemplateKind::Dir) => extract_template_metadata_from_dir(path),
+            Some(_) => extract_template_metadata(path),
+            None => None,
+        };
+        let mut state = self.state.lock().unwrap();
+        state.entries.insert(key, IndexEntry { size, mtime, kind, metadata: metadata.clone() });
+        state.dirty = true;
+        kind.map(|kind| (kind, metadata))
+    }
+
+    /// Drop the entries of templates under `dirs` that were not seen since loading
+    /// (removed templates), then write the index if it changed.
+    pub fn save(&self, dirs: &[PathBuf]) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let IndexState { entries, seen, dirty } = &mut *state;
+        let before = entries.len();
+        entries.retain(|key, _| seen.contains(key) || !dirs.iter().any(|d| Path::new(key).starts_with(d)));
+
+        if !*dirty && entries.len() == before {
+            return Ok(());
+        }
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+
+        let mut map = Map::new();
+        let mut keys: Vec<&String> = entries.keys().collect();
+        keys.sort();
+        for key in keys {
+            let entry = &entries[key];
+            map.insert(
+                key.clone(),
+                json!({
+                    "size": entry.size,
+                    "mtime": entry.mtime.to_string(),
+                    "kind": entry.kind.map(|k| k.as_str()),
+                    "metadata": entry.metadata,
+                }),
+            );
+        }
+        let index = json!({ "version": INDEX_VERSION, "entries": map });
+
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(file, serde_json::to_string_pretty(&index)?)?;
+        *dirty = false;
+        Ok(())
+    }
+
+    /// Save the index, only warning on failure: the cache is an optimisation
+    pub fn save_or_warn(&self, dirs: &[PathBuf]) {
+        if let Err(e) = self.save(dirs) {
+            log_warning(&format!("Unable to write the template index: {}", e));
+        }
+    }
+
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+}
+
+fn parse_entries(index: &Value) -> HashMap<String, IndexEntry> {
+    index
+        .get("entries")
+        .and_then(|v| v.as_object())
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|(key, value)| {
+                    let size = value.get("size")?.as_u64()?;
+                    let mtime = value.get("mtime")?.as_str()?.parse().ok()?;
+                    let kind = match value.get("kind")? {
+                        Value::Null => None,
+                        kind => Some(TemplateKind::parse(kind.as_str()?)?),
+                    };
+                    let metadata = value.get("metadata").filter(|m| !m.is_null()).cloned();
+                    Some((key.clone(), IndexEntry { size, mtime, kind, metadata }))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Size and modification time (nanoseconds since the epoch) of a file
+fn file_stamp(path: &Path) -> Option<(u64, u128)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    Some((meta.len(), mtime))
+}
+
+fn index_file() -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| d.join("genj").join("index.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_kind_is_trusted_while_the_file_is_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("template.bin");
+        fs::write(&path, "not an archive").unwrap();
+        let (size, mtime) = file_stamp(&path).unwrap();
+        let entry = IndexEntry { size, mtime, kind: Some(TemplateKind::Archive(ArchiveFormat::TarGz)), metadata: None };
+        let index = TemplateIndex::with_entries(None, HashMap::from([(path.display().to_string(), entry)]));
+
+        // the file is not opened again
+        assert_eq!(index.template(&path).map(|(kind, _)| kind), Some(TemplateKind::Archive(ArchiveFormat::TarGz)));
+        fs::write(&path, "still not an archive").unwrap();
+        assert_eq!(index.template(&path), None);
+    }
+
+    #[test]
+    fn kinds_are_saved_and_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().join("folder");
+        fs::create_dir(&folder).unwrap();
+        fs::write(folder.join(".template"), r#"{"description": "A folder"}"#).unwrap();
+        let readme = dir.path().join("README.md");
+        fs::write(&readme, "not a template").unwrap();
+
+        let file = dir.path().join("index.json");
+        let index = TemplateIndex::with_entries(Some(file.clone()), HashMap::new());
+        assert_eq!(index.template(&folder).unwrap().0, TemplateKind::Dir);
+        assert_eq!(index.template(&readme), None);
+        index.save(&[]).unwrap();
+
+        let saved: Value = serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+        let entries = parse_entries(&saved);
+        let folder_entry = &entries[&folder.display().to_string()];
+        assert_eq!(folder_entry.kind, Some(TemplateKind::Dir));
+        assert_eq!(folder_entry.metadata, Some(json!({ "description": "A folder" })));
+        assert_eq!(entries[&readme.display().to_string()].kind, None);
+    }
+}
@@ -0,0 +1,371 @@
+use crate::cli::SignaturePolicy;
+use crate::config::UserConfig;
+use crate::log::{log_success, log_verbose, log_warning};
+use crate::archive::{for_each_entry, ArchiveFormat, EntryKind};
+use crate::template::{archive_common_prefix, is_in_dir};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand_core::OsRng;
+use sha2::{Digest, Sha256};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+/// Folder of a template holding its integrity data, never copied into projects
+pub const INTEGRITY_DIR: &str = ".genj";
+/// Every entry of the template (type, sha256 of files, modes, link targets),
+/// relative to the template root
+pub const MANIFEST_FILE: &str = ".genj/manifest.json";
+/// ed25519 signature of the exact bytes of the manifest
+pub const SIGNATURE_FILE: &str = ".genj/manifest.sig";
+
+/// Version of the manifest format: 1 only listed the sha256 of regular files
+const MANIFEST_VERSION: u64 = 2;
+
+/// Default modes applied on extraction when the archive records none
+const DEFAULT_FILE_MODE: u32 = 0o644;
+const DEFAULT_DIR_MODE: u32 = 0o755;
+
+/// One entry of the manifest, as it will be extracted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestEntry {
+    File { sha256: String, mode: u32 },
+    Dir { mode: u32 },
+    Symlink { target: String },
+}
+
+impl ManifestEntry {
+    fn to_json(&self) -> Value {
+        match self {
+            ManifestEntry::File { sha256, mode } => json!({ "type": "file", "sha256": sha256, "mode": format!("{:o}", mode) }),
+            ManifestEntry::Dir { mode } => json!({ "type": "dir", "mode": format!("{:o}", mode) }),
+            ManifestEntry::Symlink { target } => json!({ "type": "symlink", "target": target }),
+        }
+    }
+
+    fn from_json(value: &Value) -> Option<ManifestEntry> {
+        let field = |name: &str| value.get(name).and_then(|v| v.as_str());
+        let mode = || field("mode").and_then(|m| u32::from_str_radix(m, 8).ok());
+        match field("type")? {
+            "file" => Some(ManifestEntry::File { sha256: field("sha256")?.to_ascii_lowercase(), mode: mode()? }),
+            "dir" => Some(ManifestEntry::Dir { mode: mode()? }),
+            "symlink" => Some(ManifestEntry::Symlink { target: field("target")?.to_string() }),
+            _ => None,
+        }
+    }
+}
+
+/// Build the manifest of a template from its entries (relative path, entry)
+pub fn build_manifest(entries: &BTreeMap<String, ManifestEntry>) -> Vec<u8> {
+    let entries: Map<String, Value> = entries.iter().map(|(k, v)| (k.clone(), v.to_json())).collect();
+    let manifest = json!({ "version": MANIFEST_VERSION, "algorithm": "sha256", "entries": entries });
+    serde_json::to_vec_pretty(&manifest).unwrap_or_default()
+}
+
+/// Manifest entry of a file, `mode` being its Unix permissions if known
+pub fn file_entry(data: &[u8], mode: Option<u32>) -> ManifestEntry {
+    ManifestEntry::File { sha256: to_hex(&Sha256::digest(data)), mode: mode.unwrap_or(DEFAULT_FILE_MODE) }
+}
+
+/// Manifest entry of a directory, `mode` being its Unix permissions if known
+pub fn dir_entry(mode: Option<u32>) -> ManifestEntry {
+    ManifestEntry::Dir { mode: mode.unwrap_or(DEFAULT_DIR_MODE) }
+}
+
+/// Sign a manifest with a secret key file (32 bytes, hex encoded).
+/// Returns the content of the signature file.
+pub fn sign_manifest(manifest: &[u8], key_file: &Path) -> io::Result<Vec<u8>> {
+    let key = read_signing_key(key_file)?;
+    let signature = key.sign(manifest);
+    let content = json!({
+        "algorithm": "ed25519",
+        "public_key": to_hex(key.verifying_key().as_bytes()),
+        "signature": to_hex(&signature.to_bytes()),
+    });
+    Ok(serde_json::to_vec_pretty(&content).unwrap_or_default())
+}
+
+/// Generate a key pair: the secret key is written to `key_file` and the
+/// public key to `<key_file>.pub`, both hex encoded. Returns the public key.
+pub fn generate_keypair(key_file: &Path) -> io::Result<String> {
+    let key = SigningKey::generate(&mut OsRng);
+    let public = to_hex(key.verifying_key().as_bytes());
+    std::fs::write(key_file, format!("{}\n", to_hex(key.as_bytes())))?;
+    let mut public_file = key_file.as_os_str().to_owned();
+    public_file.push(".pub");
+    std::fs::write(public_file, format!("{}\n", public))?;
+    Ok(public)
+}
+
+/// Check the manifest and signature of a template archive before it is extracted.
+///
+/// A manifest that does not match the archive content, or an invalid
+/// signature, is always an error (unless the policy is `ignore`). Unsigned
+/// templates, or templates signed by an untrusted key, are reported according
+/// to the policy: `--signature-policy`, else `signature_policy` in the user
+/// configuration, else `warn`. Trusted keys are the hex encoded ed25519
+/// public keys listed in `trusted_keys`.
+pub fn verify_template_archive(archive_path: &Path, format: ArchiveFormat, policy: Option<SignaturePolicy>, verbose: bool) -> io::Result<()> {
+    let config = UserConfig::load();
+    let policy = policy
+        .or_else(|| config.get_str("signature_policy").and_then(SignaturePolicy::parse))
+        .unwrap_or(SignaturePolicy::Warn);
+    if policy == SignaturePolicy::Ignore {
+        log_verbose("Template signature verification disabled", verbose);
+        return Ok(());
+    }
+
+    let contents = read_archive_contents(archive_path, format)?;
+    let Some(manifest) = &contents.manifest else {
+        return untrusted(policy, "Template has no integrity manifest (unsigned)");
+    };
+    check_manifest(manifest, &contents)?;
+    log_verbose("Template content matches its manifest", verbose);
+
+    let Some(signature) = &contents.signature else {
+        return untrusted(policy, "Template manifest is not signed");
+    };
+    let public_key = check_signature(manifest, signature)?;
+
+    let trusted = config.get_strings("trusted_keys");
+    if !trusted.iter().any(|k| k.trim().eq_ignore_ascii_case(&public_key)) {
+        return untrusted(policy, &format!("Template is signed by an untrusted key: {}", public_key));
+    }
+    log_success(&format!("Template signature verified (key {})", public_key));
+    Ok(())
+}
+
+fn untrusted(policy: SignaturePolicy, message: &str) -> io::Result<()> {
+    match policy {
+        SignaturePolicy::Require => Err(io::Error::new(io::ErrorKind::PermissionDenied, message.to_string())),
+        SignaturePolicy::Warn => {
+            log_warning(message);
+            Ok(())
+        }
+        SignaturePolicy::Ignore => Ok(()),
+    }
+}
+
+/// Every entry of the archive must be listed with the same type, checksum, mode
+/// or link target, and every listed entry must exist
+fn check_manifest(manifest: &[u8], contents: &ArchiveContents) -> io::Result<()> {
+    let manifest: Value = serde_json::from_slice(manifest)
+        .map_err(|e| invalid(format!("Invalid template manifest: {}", e)))?;
+    let version = manifest.get("version").and_then(|v| v.as_u64()).unwrap_or_default();
+    if version != MANIFEST_VERSION {
+        return Err(invalid(format!("Unsupported template manifest version: {} (expected {})", version, MANIFEST_VERSION)));
+    }
+    let listed = manifest
+        .get("entries")
+        .and_then(|v| v.as_object())
+        .ok_or_else(|| invalid("Invalid template manifest: missing \"entries\"".to_string()))?;
+
+    if let Some(name) = contents.rejected.first() {
+        return Err(invalid(format!("Duplicate or unsupported entry in template: {}", name)));
+    }
+    for (name, entry) in &contents.entries {
+        let Some(value) = listed.get(name) else {
+            return Err(invalid(format!("Entry not listed in the template manifest: {}", name)));
+        };
+        let expected = ManifestEntry::from_json(value)
+            .ok_or_else(|| invalid(format!("Invalid template manifest entry: {}", name)))?;
+        match (&expected, entry) {
+            (ManifestEntry::File { sha256: a, .. }, ManifestEntry::File { sha256: b, .. }) if a != b => {
+                return Err(invalid(format!("Checksum mismatch in template: {}", name)));
+            }
+            _ if expected != *entry => {
+                return Err(invalid(format!("Type, mode or link target mismatch in template: {}", name)));
+            }
+            _ => {}
+        }
+    }
+    if let Some(missing) = listed.keys().find(|name| !contents.entries.contains_key(*name)) {
+        return Err(invalid(format!("Entry listed in the template manifest is missing: {}", missing)));
+    }
+    Ok(())
+}
+
+/// Verify the signature of the manifest and return the (hex) public key that signed it
+fn check_signature(manifest: &[u8], signature: &[u8]) -> io::Result<String> {
+    let signature: Value = serde_json::from_slice(signature)
+        .map_err(|e| invalid(format!("Invalid template signature file: {}", e)))?;
+    let field = |name: &str| {
+        signature
+            .get(name)
+            .and_then(|v| v.as_str())
+            .and_then(from_hex)
+            .ok_or_else(|| invalid(format!("Invalid template signature file: bad \"{}\"", name)))
+    };
+
+    let key_bytes: [u8; 32] = field("public_key")?
+        .try_into()
+        .map_err(|_| invalid("Invalid template signature: bad public key length".to_string()))?;
+    let sig_bytes: [u8; 64] = field("signature")?
+        .try_into()
+        .map_err(|_| invalid("Invalid template signature: bad signature length".to_string()))?;
+
+    let key = VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|e| invalid(format!("Invalid template signature key: {}", e)))?;
+    key.verify(manifest, &Signature::from_bytes(&sig_bytes))
+        .map_err(|_| invalid("Template signature does not match its manifest".to_string()))?;
+    Ok(to_hex(&key_bytes))
+}
+
+/// Content of a template archive as seen by the integrity checks
+struct ArchiveContents {
+    /// Every entry outside of `.genj/`, keyed by its path relative to the template root
+    entries: BTreeMap<String, ManifestEntry>,
+    /// Entries that no manifest can list: duplicated names, hard links, devices…
+    rejected: Vec<String>,
+    manifest: Option<Vec<u8>>,
+    signature: Option<Vec<u8>>,
+}
+
+fn read_archive_contents(archive_path: &Path, format: ArchiveFormat) -> io::Result<ArchiveContents> {
+    let mut entry_names = Vec::new();
+    for_each_entry(archive_path, format, |entry| {
+        entry_names.push(entry.name);
+        Ok(true)
+    })?;
+    let prefix = archive_common_prefix(&entry_names).unwrap_or_default();
+
+    let mut contents = ArchiveContents { entries: BTreeMap::new(), rejected: Vec::new(), manifest: None, signature: None };
+    for_each_entry(archive_path, format, |entry| {
+        let name = entry.name.strip_prefix(prefix.as_str()).unwrap_or(&entry.name);
+        let name = name.trim_start_matches("./").trim_end_matches('/').to_string();
+        // the root folder of the template itself
+        if name.is_empty() && entry.kind == EntryKind::Dir {
+            return Ok(true);
+        }
+        if is_in_dir(&name, INTEGRITY_DIR) {
+            if entry.kind != EntryKind::File {
+                return Ok(true);
+            }
+            let mut bytes = Vec::new();
+            entry.reader.read_to_end(&mut bytes)?;
+            match name.as_str() {
+                MANIFEST_FILE => contents.manifest = Some(bytes),
+                SIGNATURE_FILE => contents.signature = Some(bytes),
+                _ => {}
+            }
+            return Ok(true);
+        }
+        let listed = match entry.kind {
+            EntryKind::File => {
+                let mut hasher = Sha256::new();
+                io::copy(entry.reader, &mut hasher)?;
+                ManifestEntry::File { sha256: to_hex(&hasher.finalize()), mode: entry.mode.unwrap_or(DEFAULT_FILE_MODE) }
+            }
+            EntryKind::Dir => dir_entry(entry.mode),
+            EntryKind::Symlink(target) => ManifestEntry::Symlink { target },
+            EntryKind::Other => {
+                contents.rejected.push(name);
+                return Ok(true);
+            }
+        };
+        if contents.entries.insert(name.clone(), listed).is_some() {
+            contents.rejected.push(name);
+        }
+        Ok(true)
+    })?;
+    Ok(contents)
+}
+
+fn read_signing_key(key_file: &Path) -> io::Result<SigningKey> {
+    let content = std::fs::read_to_string(key_file)?;
+    let bytes: [u8; 32] = from_hex(content.trim())
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| invalid(format!("Invalid signing key (expected 32 hex encoded bytes): {}", key_file.display())))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|h| u8::from_str_radix(h, 16).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::ArchiveWriter;
+
+    /// Write a tar.gz template with a build script, a folder and a link, its
+    /// manifest listing them, then `extra` entries unknown to the manifest
+    fn signed_archive(path: &Path, extra: impl FnOnce(&mut ArchiveWriter)) -> io::Result<()> {
+        let mut archive = ArchiveWriter::create(path, ArchiveFormat::TarGz)?;
+        let mut entries = BTreeMap::new();
+        archive.add_file("build", b"#!/bin/sh\n", Some(0o755))?;
+        entries.insert("build".to_string(), file_entry(b"#!/bin/sh\n", Some(0o755)));
+        archive.add_dir("src", None)?;
+        entries.insert("src".to_string(), dir_entry(None));
+        archive.add_symlink("run", "build")?;
+        entries.insert("run".to_string(), ManifestEntry::Symlink { target: "build".to_string() });
+        archive.add_file(MANIFEST_FILE, &build_manifest(&entries), None)?;
+        extra(&mut archive);
+        archive.finish()
+    }
+
+    fn verify(path: &Path) -> io::Result<()> {
+        let contents = read_archive_contents(path, ArchiveFormat::TarGz)?;
+        check_manifest(contents.manifest.as_deref().unwrap(), &contents)
+    }
+
+    #[test]
+    fn manifest_lists_every_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("template.tar.gz");
+        signed_archive(&path, |_| {}).unwrap();
+        verify(&path).unwrap();
+    }
+
+    #[test]
+    fn unlisted_symlink_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("template.tar.gz");
+        signed_archive(&path, |archive| archive.add_symlink("evil-link", "build").unwrap()).unwrap();
+        let error = verify(&path).unwrap_err();
+        assert!(error.to_string().contains("not listed"), "{}", error);
+    }
+
+    #[test]
+    fn changed_mode_or_duplicate_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("template.tar.gz");
+        signed_archive(&path, |archive| archive.add_file("build", b"#!/bin/sh\n", Some(0o644)).unwrap()).unwrap();
+        assert!(verify(&path).unwrap_err().to_string().contains("Duplicate"));
+
+        let mut archive = ArchiveWriter::create(&path, ArchiveFormat::TarGz).unwrap();
+        archive.add_file("build", b"#!/bin/sh\n", Some(0o644)).unwrap();
+        let entries = BTreeMap::from([("build".to_string(), file_entry(b"#!/bin/sh\n", Some(0o755)))]);
+        archive.add_file(MANIFEST_FILE, &build_manifest(&entries), None).unwrap();
+        archive.finish().unwrap();
+        assert!(verify(&path).unwrap_err().to_string().contains("mode"));
+    }
+
+    #[test]
+    fn changed_link_target_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("template.zip");
+        let mut archive = ArchiveWriter::create(&path, ArchiveFormat::Zip).unwrap();
+        archive.add_symlink("run", "..").unwrap();
+        let entries = BTreeMap::from([("run".to_string(), ManifestEntry::Symlink { target: "build".to_string() })]);
+        archive.add_file(MANIFEST_FILE, &build_manifest(&entries), None).unwrap();
+        archive.finish().unwrap();
+        let contents = read_archive_contents(&path, ArchiveFormat::Zip).unwrap();
+        let error = check_manifest(contents.manifest.as_deref().unwrap(), &contents).unwrap_err();
+        assert!(error.to_string().contains("link target"), "{}", error);
+    }
+}
@@ -1,24 +1,52 @@
+pub mod archive;
 pub mod cli;
 pub mod log;
 pub mod fs;
 pub mod template;
+pub mod placeholder;
+pub mod progress;
+pub mod text;
+pub mod catalog;
+pub mod index;
+pub mod config;
+pub mod registry;
+pub mod integrity;
+pub mod query;
 pub mod genrc;
 pub mod vscode_git;
+pub mod harness;
+pub mod ide;
+pub mod license;
+pub mod project_files;
+pub mod forge;
+pub mod ci;
+pub mod container;
+#[cfg(test)]
+mod test_support;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 use crate::cli::Cli;
 use crate::log::{log_info, log_verbose, log_success, log_warning};
-use crate::template::process_template;
+use crate::template::{extract_template_metadata, extract_template_metadata_from_dir, process_template};
+use crate::ci::write_ci;
+use crate::container::write_container_files;
+use crate::project_files::{main_class, write_manifest, write_readme};
 use crate::genrc::write_genrc;
-use crate::vscode_git::setup_vscode_and_git;
+use crate::forge::ForgeClient;
+use crate::vscode_git::{check_branch_name, check_enclosing_repository, check_remote, push_to_remote, set_origin, setup_vscode_and_git};
+use crate::registry::{fetch_template, REGISTRY_PREFIX};
+use crate::archive::ArchiveFormat;
+use crate::integrity::verify_template_archive;
 use chrono::prelude::*;
 use std::io;
 use std::path::{PathBuf, Path};
 
-/// Resolve template path from CLI option or default search paths
-fn resolve_template_path(template_opt: &Option<String>) -> io::Result<PathBuf> {
+/// Resolve template path from CLI option or default search paths.
+/// `registry:<name>[@<version>]` templates are downloaded into the cache first.
+fn resolve_template_path(template_opt: &Option<String>, verbose: bool) -> io::Result<PathBuf> {
     match template_opt {
+        Some(t) if t.starts_with(REGISTRY_PREFIX) => fetch_template(t, verbose),
         Some(t) => {
             let path = Path::new(t);
             if path.exists() {
@@ -54,7 +82,7 @@ pub fn run(cli: Cli) -> io::Result<()> {
     }
 
     // Resolve template path
-    let template_path = resolve_template_path(&cli.template)?;
+    let template_path = resolve_template_path(&cli.template, cli.verbose)?;
     let mut dest_path = resolve_destination_path(&cli.destination);
     dest_path.push(&cli.project_name);
 
@@ -63,20 +91,55 @@ pub fn run(cli: Cli) -> io::Result<()> {
 
     // Replacements array
     let current_year = Utc::now().year().to_string();
+    let package_path = cli.package.replace('.', "/");
     let replacements = [
         ("${PROJECT_NAME}", cli.project_name.as_str()),
         ("${AUTHOR_NAME}", cli.author.as_str()),
         ("${AUTHOR_EMAIL}", cli.email.as_str()),
         ("${PROJECT_VERSION}", cli.project_version.as_str()),
         ("${PACKAGE}", cli.package.as_str()),
+        ("${PACKAGE_PATH}", package_path.as_str()),
         ("${JAVA}", cli.java.as_str()),
         ("${VENDOR_NAME}", cli.vendor_name.as_str()),
         ("${MAINCLASS}", cli.mainclass.as_str()),
         ("${PROJECT_YEAR}", current_year.as_str()),
     ];
 
+    if !cli.no_git {
+        check_branch_name(&cli.branch)?;
+        check_enclosing_repository(&dest_path, &cli)?;
+    }
+    if let (true, Some(url)) = (cli.push, &cli.remote_git) {
+        check_remote(url, cli.verbose)?;
+    }
+    let forge = match cli.create_remote {
+        Some(forge) => {
+            forge.check_visibility(cli.remote_owner.as_deref(), cli.remote_visibility)?;
+            Some(ForgeClient::connect(forge, &cli)?)
+        }
+        None => None,
+    };
+
+    if template_path.is_file() {
+        if let Some(format) = ArchiveFormat::detect(&template_path)? {
+            verify_template_archive(&template_path, format, cli.signature_policy, cli.verbose)?;
+        }
+    }
+
+    let java_header = match cli.license {
+        Some(license) if cli.license_header => Some(license.java_header(&current_year, &cli.author)),
+        _ => None,
+    };
+
     log_info(&format!("Reading template from: {}", template_path.display()));
-    process_template(&template_path, &dest_path, &replacements, cli.verbose)?;
+    process_template(&template_path, &dest_path, &replacements, java_header.as_deref(), cli.strict, cli.verbose)?;
+
+    // LICENSE, replacing the one of the template
+    if let Some(license) = cli.license {
+        log_verbose(&format!("Generating LICENSE ({})", license.spdx_id()), cli.verbose);
+        std::fs::write(dest_path.join("LICENSE"), license.license_file(&replacements))?;
+        log_success(&format!("LICENSE generated ({})", license.spdx_id()));
+    }
 
     // Build files (.pom / build.gradle) and .sdkmanrc
     let build_tool = cli.build_tool.to_lowercase();
@@ -95,7 +158,7 @@ pub fn run(cli: Cli) -> io::Result<()> {
   <groupId>{}</groupId>
   <artifactId>{}</artifactId>
   <version>{}</version>
-  <properties>
+{}  <properties>
     <maven.compiler.target>{}</maven.compiler.target>
     <maven.compiler.source>{}</maven.compiler.source>
   </properties>
@@ -108,7 +171,7 @@ pub fn run(cli: Cli) -> io::Result<()> {
         <configuration>
           <archive>
             <manifest>
-              <mainClass>{}.{}</mainClass>
+              <mainClass>{}</mainClass>
             </manifest>
           </archive>
         </configuration>
@@ -116,7 +179,13 @@ pub fn run(cli: Cli) -> io::Result<()> {
     </plugins>
   </build>
 </project>"#,
-            cli.package, cli.project_name, cli.project_version, cli.java, cli.java, cli.package, cli.mainclass  
+            cli.package,
+            cli.project_name,
+            cli.project_version,
+            cli.license.map(|l| l.pom_block()).unwrap_or_default(),
+            cli.java,
+            cli.java,
+            main_class(&cli)
         );
         std::fs::create_dir_all(pom_path.parent().unwrap_or(&dest_path))?;
         std::fs::write(pom_path, pom_content)?;
@@ -154,15 +223,45 @@ repositories {{
     std::fs::write(sdkman_file, sdkman_content)?;
     log_success(".sdkmanrc generated");
 
+    // MANIFEST.MF and README.md, unless the template has its own
+    log_verbose("Generating MANIFEST.MF and README.md", cli.verbose);
+    write_manifest(&dest_path, &cli)?;
+    let metadata = if template_path.is_file() {
+        extract_template_metadata(&template_path)
+    } else {
+        extract_template_metadata_from_dir(&template_path)
+    };
+    write_readme(&dest_path, &cli, metadata.as_ref(), &build_tool)?;
+    if !cli.ci.is_empty() {
+        log_verbose("Generating CI pipelines", cli.verbose);
+        write_ci(&dest_path, &cli)?;
+    }
+    if cli.container {
+        log_verbose("Generating container files", cli.verbose);
+        write_container_files(&dest_path, &cli)?;
+    }
+
     // .genrc
     log_verbose("Generating .genrc", cli.verbose);
     write_genrc(&dest_path, &cli)?;
     log_success(".genrc configuration file generated");
 
     // VSCode + Git
-    log_info("Configuring VSCode and Git repository...");
-    if let Err(e) = setup_vscode_and_git(&dest_path, &cli) {
-        log_warning(&format!("Error during VSCode/Git configuration: {}", e));
+    log_info("Configuring IDE and Git repository...");
+    if let Err(e) = setup_vscode_and_git(&dest_path, &cli, &replacements) {
+        // nothing can be pushed without the repository: a warning would look like a success
+        if cli.push || cli.create_remote.is_some() {
+            return Err(io::Error::new(e.kind(), format!("IDE/Git configuration failed, nothing pushed: {}", e)));
+        }
+        log_warning(&format!("Error during IDE/Git configuration: {}", e));
+    } else if let Some(forge) = &forge {
+        let description = metadata.as_ref().and_then(|m| m.get("description")).and_then(|v| v.as_str()).unwrap_or_default();
+        let repository = forge.create_repository(&cli.project_name, description, cli.remote_owner.as_deref(), cli.remote_visibility)?;
+        set_origin(&dest_path, &repository.clone_url, cli.verbose)?;
+        push_to_remote(&dest_path, &cli, Some((forge.username.as_str(), forge.token())))?;
+    } else if cli.push {
+        // the project is there, but a failed push must not look like a success
+        push_to_remote(&dest_path, &cli, None)?;
     }
 
     log_success(&format!("Java project '{}' generated successfully in {}", cli.project_name, dest_path.display()));
@@ -0,0 +1,148 @@
+use crate::placeholder::{Delimiters, Renderer};
+use clap::ValueEnum;
+
+/// Licenses of the generated projects (`--license`), with their SPDX text embedded
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum License {
+    #[value(name = "MIT")]
+    Mit,
+    #[value(name = "Apache-2.0")]
+    Apache2,
+    #[value(name = "GPL-3.0-only")]
+    Gpl3Only,
+    /// All rights reserved
+    #[value(name = "proprietary")]
+    Proprietary,
+}
+
+impl License {
+    /// SPDX identifier, as written in `SPDX-License-Identifier` headers
+    pub fn spdx_id(&self) -> &'static str {
+        match self {
+            License::Mit => "MIT",
+            License::Apache2 => "Apache-2.0",
+            License::Gpl3Only => "GPL-3.0-only",
+            License::Proprietary => "LicenseRef-Proprietary",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            License::Mit => "MIT License",
+            License::Apache2 => "Apache License, Version 2.0",
+            License::Gpl3Only => "GNU General Public License v3.0 only",
+            License::Proprietary => "Proprietary",
+        }
+    }
+
+    pub fn url(&self) -> Option<&'static str> {
+        match self {
+            License::Mit => Some("https://opensource.org/licenses/MIT"),
+            License::Apache2 => Some("https://www.apache.org/licenses/LICENSE-2.0.txt"),
+            License::Gpl3Only => Some("https://www.gnu.org/licenses/gpl-3.0.txt"),
+            License::Proprietary => None,
+        }
+    }
+
+    fn text(&self) -> &'static str {
+        match self {
+            License::Mit => include_str!("licenses/MIT.txt"),
+            License::Apache2 => include_str!("licenses/Apache-2.0.txt"),
+            License::Gpl3Only => include_str!("licenses/GPL-3.0-only.txt"),
+            License::Proprietary => include_str!("licenses/proprietary.txt"),
+        }
+    }
+
+    /// Content of the `LICENSE` file. Texts that must stay verbatim (Apache, GPL)
+    /// are preceded by the copyright line instead of being filled in.
+    pub fn license_file(&self, replacements: &[(&str, &str)]) -> String {
+        let renderer = Renderer::new(replacements, &Delimiters::default());
+        let text = self.text();
+        if text.contains("${PROJECT_YEAR}") {
+            renderer.render(text).0
+        } else {
+            let copyright = renderer.render("Copyright (c) ${PROJECT_YEAR} ${AUTHOR_NAME}").0;
+            format!("{}\n\n{}", copyright, text.trim_start_matches('\n'))
+        }
+    }
+
+    /// `<licenses>` block of the POM, indented for `<project>`
+    pub fn pom_block(&self) -> String {
+        let url = self.url().map(|u| format!("\n      <url>{}</url>", u)).unwrap_or_default();
+        // proprietary artifacts are not meant to be downloaded from public repositories
+        let distribution = if *self == License::Proprietary { "manual" } else { "repo" };
+        format!(
+            "  <licenses>\n    <license>\n      <name>{}</name>{}\n      <distribution>{}</distribution>\n    </license>\n  </licenses>\n",
+            self.name(),
+            url,
+            distribution
+        )
+    }
+
+    /// SPDX header comment of the generated Java sources
+    pub fn java_header(&self, year: &str, author: &str) -> String {
+        format!(
+            "/*\n * SPDX-License-Identifier: {}\n * Copyright (c) {} {}\n */\n",
+            self.spdx_id(),
+            year,
+            author
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [License; 4] = [License::Mit, License::Apache2, License::Gpl3Only, License::Proprietary];
+    const REPLACEMENTS: [(&str, &str); 2] = [("${PROJECT_YEAR}", "2026"), ("${AUTHOR_NAME}", "Jane Doe")];
+
+    #[test]
+    fn license_file_carries_the_copyright() {
+        for license in ALL {
+            let file = license.license_file(&REPLACEMENTS);
+            assert!(file.contains("Copyright (c) 2026 Jane Doe"), "{:?}", license);
+            assert!(!file.contains("${"), "{:?}", license);
+        }
+        assert!(License::Mit.license_file(&REPLACEMENTS).starts_with("MIT License\n\nCopyright (c) 2026 Jane Doe\n"));
+        // verbatim texts get the copyright line first
+        let apache = License::Apache2.license_file(&REPLACEMENTS);
+        assert!(apache.starts_with("Copyright (c) 2026 Jane Doe\n\n"), "{}", &apache[..80]);
+        assert!(apache.contains("Apache License\n                           Version 2.0, January 2004"));
+        let gpl = License::Gpl3Only.license_file(&REPLACEMENTS);
+        assert!(gpl.starts_with("Copyright (c) 2026 Jane Doe\n\n                    GNU GENERAL PUBLIC LICENSE"));
+        assert!(License::Proprietary.license_file(&REPLACEMENTS).starts_with("Copyright (c) 2026 Jane Doe. All rights reserved."));
+    }
+
+    #[test]
+    fn pom_block_per_license() {
+        assert_eq!(
+            License::Apache2.pom_block(),
+            "  <licenses>\n    <license>\n      <name>Apache License, Version 2.0</name>\n      \
+             <url>https://www.apache.org/licenses/LICENSE-2.0.txt</url>\n      <distribution>repo</distribution>\n    \
+             </license>\n  </licenses>\n"
+        );
+        assert!(License::Mit.pom_block().contains("<url>https://opensource.org/licenses/MIT</url>"));
+        assert!(License::Gpl3Only.pom_block().contains("<name>GNU General Public License v3.0 only</name>"));
+        let proprietary = License::Proprietary.pom_block();
+        assert!(!proprietary.contains("<url>"), "{}", proprietary);
+        assert!(proprietary.contains("<distribution>manual</distribution>"), "{}", proprietary);
+    }
+
+    #[test]
+    fn java_header_uses_the_spdx_identifier() {
+        assert_eq!(
+            License::Gpl3Only.java_header("2026", "Jane Doe"),
+            "/*\n * SPDX-License-Identifier: GPL-3.0-only\n * Copyright (c) 2026 Jane Doe\n */\n"
+        );
+        for license in ALL {
+            let id = license.spdx_id();
+            assert!(license.java_header("2026", "Jane Doe").contains(&format!("SPDX-License-Identifier: {}\n", id)));
+            // the identifiers are also the --license values, except for the LicenseRef
+            if license != License::Proprietary {
+                assert_eq!(license.to_possible_value().unwrap().get_name(), id);
+            }
+        }
+        assert_eq!(License::Proprietary.spdx_id(), "LicenseRef-Proprietary");
+    }
+}
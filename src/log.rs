@@ -11,4 +11,7 @@ pub fn log_success(msg: &str) {
 }
 pub fn log_warning(msg: &str) {
     eprintln!("[⚠] {}", msg);
+}
+pub fn log_error(msg: &str) {
+    eprintln!("[✗] {}", msg);
 }
\ No newline at end of file
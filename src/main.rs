@@ -1,19 +1,47 @@
-use genj::cli::Cli;
+use genj::cli::{Cli, Command, TemplateCommand};
+use genj::harness::test_template;
 use genj::run;
-use genj::template::{list_available_templates, search_templates};
+use genj::catalog::{list_available_templates, reindex_templates, search_templates};
 
 fn main() {
     let cli = Cli::parse();
+
+    // Handle subcommands
+    if let Some(Command::Template { action }) = cli.command.as_ref() {
+        match action {
+            TemplateCommand::Test { path, bless } => match test_template(path, *bless, cli.verbose) {
+                Ok(true) => return,
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            TemplateCommand::Reindex => {
+                if let Err(e) = reindex_templates() {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+        }
+    }
     
     // Handle --list option
     if cli.list {
-        list_available_templates();
+        if let Err(e) = list_available_templates(cli.format) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
         return;
     }
 
     // Handle --search option
     if let Some(search_term) = cli.search.as_ref() {
-        search_templates(search_term);
+        if let Err(e) = search_templates(search_term, cli.format) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
         return;
     }
 
@@ -0,0 +1,293 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Delimiters of the placeholders of a template, `${` and `}` by default.
+///
+/// Declared in `.template` as `"delimiters": ["@@", "@@"]`. A placeholder
+/// preceded by the first character of the opening delimiter is an escape and
+/// renders as the literal placeholder: `$${PACKAGE}` gives `${PACKAGE}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delimiters {
+    pub open: String,
+    pub close: String,
+}
+
+impl Default for Delimiters {
+    fn default() -> Delimiters {
+        Delimiters { open: "${".to_string(), close: "}".to_string() }
+    }
+}
+
+impl Delimiters {
+    /// Read `["open", "close"]`. Delimiters must be non-empty and cannot contain
+    /// `/`, since placeholders are also used in paths.
+    pub fn parse(value: &Value) -> Option<Delimiters> {
+        let items = value.as_array()?;
+        let [open, close] = items.as_slice() else {
+            return None;
+        };
+        let (open, close) = (open.as_str()?, close.as_str()?);
+        if [open, close].iter().any(|d| d.is_empty() || d.contains(['/', '\\'])) {
+            return None;
+        }
+        Some(Delimiters { open: open.to_string(), close: close.to_string() })
+    }
+
+    /// The placeholder for a variable name, e.g. `${PACKAGE}`
+    pub fn wrap(&self, name: &str) -> String {
+        format!("{}{}{}", self.open, name, self.close)
+    }
+
+    fn escape_char(&self) -> char {
+        self.open.chars().next().unwrap_or('$')
+    }
+}
+
+/// A placeholder found in a template without a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownPlaceholder {
+    pub name: String,
+    /// 1-based line number
+    pub line: usize,
+}
+
+/// A placeholder token in a text: `open NAME close`, where NAME is made of `A-Z`, `0-9` and `_`
+struct Token<'a> {
+    start: usize,
+    end: usize,
+    name: &'a str,
+    escaped: bool,
+}
+
+/// Iterate over the placeholder tokens of `text`, in order
+fn tokens<'a>(text: &'a str, delimiters: &'a Delimiters) -> impl Iterator<Item = Token<'a>> + 'a {
+    let escape = delimiters.escape_char();
+    let mut pos = 0;
+    // end of the previous token: its last character cannot escape the next one
+    let mut previous_end = 0;
+    std::iter::from_fn(move || {
+        while let Some(found) = text[pos..].find(delimiters.open.as_str()) {
+            let start = pos + found;
+            let name_start = start + delimiters.open.len();
+            let name_len = text[name_start..]
+                .find(|c: char| !(c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'))
+                .unwrap_or(text.len() - name_start);
+            let name_end = name_start + name_len;
+            if name_len == 0 || !text[name_end..].starts_with(delimiters.close.as_str()) {
+                // delimiters may overlap an escape (`@@@NAME@@`): retry from the next character
+                pos = start + text[start..].chars().next().map_or(1, char::len_utf8);
+                continue;
+            }
+            let end = name_end + delimiters.close.len();
+            let escaped = start >= previous_end + escape.len_utf8() && text[..start].ends_with(escape);
+            pos = end;
+            previous_end = end;
+            let start = if escaped { start - escape.len_utf8() } else { start };
+            return Some(Token { start, end, name: &text[name_start..name_end], escaped });
+        }
+        None
+    })
+}
+
+/// Single-pass placeholder renderer: the text is scanned once and inserted
+/// values are never expanded again, whatever they contain.
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    delimiters: Delimiters,
+    values: HashMap<String, String>,
+}
+
+impl Renderer {
+    /// `replacements` are keyed by `${NAME}`, whatever the delimiters of the template
+    pub fn new(replacements: &[(&str, &str)], delimiters: &Delimiters) -> Renderer {
+        let values = replacements
+            .iter()
+            .map(|(key, value)| (key_name(key).to_string(), value.to_string()))
+            .collect();
+        Renderer { delimiters: delimiters.clone(), values }
+    }
+
+    pub fn delimiters(&self) -> &Delimiters {
+        &self.delimiters
+    }
+
+    /// Value of a variable, e.g. `value("PACKAGE")`
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Replace the known placeholders, unescape the escaped ones, and report the
+    /// placeholders without a value (left as they are).
+    pub fn render(&self, text: &str) -> (String, Vec<UnknownPlaceholder>) {
+        self.render_with(text, |literal, _, output| output.push_str(literal))
+    }
+
+    /// Render a file or folder name. Besides the delimited placeholders, the
+    /// file-name-safe forms `__name__` (lowercase) and `_NAME_` are replaced when they
+    /// are the whole name or a `.`-separated part of it, so `my_JAVA_notes.txt` is kept.
+    /// They only match known variables, so names like `__init__.py` are kept.
+    pub fn render_file_name(&self, name: &str) -> (String, Vec<UnknownPlaceholder>) {
+        self.render_with(name, |literal, offset, output| {
+            let last = literal.split('.').count() - 1;
+            for (i, part) in literal.split('.').enumerate() {
+                if i > 0 {
+                    output.push('.');
+                }
+                // a part next to a delimited placeholder is not a whole part of the name
+                let whole = (i > 0 || offset == 0) && (i < last || offset + literal.len() == name.len());
+                let value = self
+                    .values
+                    .iter()
+                    .find(|(name, _)| whole && file_name_forms(name).iter().any(|form| form == part));
+                match value {
+                    Some((_, value)) => output.push_str(value),
+                    None => output.push_str(part),
+                }
+            }
+        })
+    }
+
+    /// Whether a whole file or folder name is the placeholder of `name`, in any form
+    pub fn is_placeholder(&self, file_name: &str, name: &str) -> bool {
+        file_name == self.delimiters.wrap(name) || file_name_forms(name).iter().any(|form| form == file_name)
+    }
+
+    /// Single pass over the placeholder tokens; the text between them goes through
+    /// `literal` along with its offset in `text`
+    fn render_with<F>(&self, text: &str, literal: F) -> (String, Vec<UnknownPlaceholder>)
+    where
+        F: Fn(&str, usize, &mut String),
+    {
+        let mut output = String::with_capacity(text.len());
+        let mut unknown = Vec::new();
+        let mut copied = 0;
+        let mut line = 1;
+        let mut counted = 0;
+
+        for token in tokens(text, &self.delimiters) {
+            literal(&text[copied..token.start], copied, &mut output);
+            copied = token.end;
+            if token.escaped {
+                output.push_str(&self.delimiters.wrap(token.name));
+                continue;
+            }
+            match self.values.get(token.name) {
+                Some(value) => output.push_str(value),
+                None => {
+                    line += text[counted..token.start].matches('\n').count();
+                    counted = token.start;
+                    unknown.push(UnknownPlaceholder { name: token.name.to_string(), line });
+                    output.push_str(&text[token.start..token.end]);
+                }
+            }
+        }
+        literal(&text[copied..], copied, &mut output);
+        (output, unknown)
+    }
+}
+
+/// File-name-safe forms of a placeholder: `__package__` and `_PACKAGE_` for `PACKAGE`
+fn file_name_forms(name: &str) -> [String; 2] {
+    [format!("__{}__", name.to_lowercase()), format!("_{}_", name)]
+}
+
+/// Variable name of a replacement key: `${PACKAGE}` gives `PACKAGE`
+pub fn key_name(key: &str) -> &str {
+    key.strip_prefix("${").and_then(|k| k.strip_suffix('}')).unwrap_or(key)
+}
+
+/// Names of the unescaped placeholders of a line
+pub fn placeholder_names<'a>(line: &'a str, delimiters: &'a Delimiters) -> Vec<&'a str> {
+    tokens(line, delimiters).filter(|t| !t.escaped).map(|t| t.name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renderer() -> Renderer {
+        let replacements = [("${PACKAGE}", "com.example"), ("${MAINCLASS}", "App"), ("${JAVA}", "25")];
+        Renderer::new(&replacements, &Delimiters::default())
+    }
+
+    fn file_name(name: &str) -> String {
+        renderer().render_file_name(name).0
+    }
+
+    #[test]
+    fn file_name_forms_replace_whole_name_parts() {
+        assert_eq!(file_name("__mainclass__.java"), "App.java");
+        assert_eq!(file_name("_MAINCLASS_.java"), "App.java");
+        assert_eq!(file_name("__package__"), "com.example");
+        assert_eq!(file_name("notes._JAVA_"), "notes.25");
+        assert_eq!(file_name("${MAINCLASS}Test.java"), "AppTest.java");
+    }
+
+    #[test]
+    fn file_name_forms_inside_a_name_part_are_kept() {
+        assert_eq!(file_name("my_JAVA_notes.txt"), "my_JAVA_notes.txt");
+        assert_eq!(file_name("__mainclass__Test.java"), "__mainclass__Test.java");
+        assert_eq!(file_name("${MAINCLASS}_JAVA_.txt"), "App_JAVA_.txt");
+        assert_eq!(file_name("__init__.py"), "__init__.py");
+    }
+
+    #[test]
+    fn whole_name_placeholders() {
+        let renderer = renderer();
+        assert!(renderer.is_placeholder("${PACKAGE}", "PACKAGE"));
+        assert!(renderer.is_placeholder("__package__", "PACKAGE"));
+        assert!(renderer.is_placeholder("_PACKAGE_", "PACKAGE"));
+        assert!(!renderer.is_placeholder("_PACKAGE_.txt", "PACKAGE"));
+    }
+
+    #[test]
+    fn delimiters_are_read_from_the_metadata() {
+        assert_eq!(
+            Delimiters::parse(&serde_json::json!(["@@", "@@"])),
+            Some(Delimiters { open: "@@".to_string(), close: "@@".to_string() })
+        );
+        for invalid in [serde_json::json!(["@@"]), serde_json::json!(["", "}"]), serde_json::json!(["{/", "}"]), serde_json::json!("@@")] {
+            assert_eq!(Delimiters::parse(&invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn custom_delimiters_replace_their_own_placeholders() {
+        let at = Delimiters { open: "@@".to_string(), close: "@@".to_string() };
+        let renderer = Renderer::new(&[("${PACKAGE}", "com.example")], &at);
+        assert_eq!(renderer.render("package @@PACKAGE@@; // ${PACKAGE}").0, "package com.example; // ${PACKAGE}");
+        assert_eq!(renderer.render("@@@PACKAGE@@ @@lower@@").0, "@@PACKAGE@@ @@lower@@");
+        // the closing delimiter of a placeholder does not escape the next one
+        assert_eq!(renderer.render("@@PACKAGE@@@@PACKAGE@@").0, "com.examplecom.example");
+
+        let braces = Delimiters { open: "{{".to_string(), close: "}}".to_string() };
+        let renderer = Renderer::new(&[("${PACKAGE}", "com.example")], &braces);
+        assert_eq!(renderer.render("{{PACKAGE}}/{{{PACKAGE}}").0, "com.example/{{PACKAGE}}");
+        assert_eq!(renderer.render_file_name("{{PACKAGE}}.txt").0, "com.example.txt");
+    }
+
+    #[test]
+    fn escaped_placeholders_are_kept_literally() {
+        let renderer = renderer();
+        let (text, unknown) = renderer.render("echo $${TARGET} $${PACKAGE} ${PACKAGE}");
+        assert_eq!(text, "echo ${TARGET} ${PACKAGE} com.example");
+        assert!(unknown.is_empty());
+        assert_eq!(placeholder_names("$${TARGET} ${PACKAGE} ${JAVA}", &Delimiters::default()), ["PACKAGE", "JAVA"]);
+    }
+
+    #[test]
+    fn unknown_placeholders_are_reported_with_their_line() {
+        let (text, unknown) = renderer().render("${PACKAGE}\n\n${TARGET} ${HOME}\r\n$${ESCAPED}\n${TARGET}");
+        assert_eq!(text, "com.example\n\n${TARGET} ${HOME}\r\n${ESCAPED}\n${TARGET}");
+        let found: Vec<(&str, usize)> = unknown.iter().map(|u| (u.name.as_str(), u.line)).collect();
+        assert_eq!(found, [("TARGET", 3), ("HOME", 3), ("TARGET", 5)]);
+    }
+
+    #[test]
+    fn inserted_values_are_not_rendered_again() {
+        let renderer = Renderer::new(&[("${AUTHOR_NAME}", "${PACKAGE} $${X}"), ("${PACKAGE}", "com.example")], &Delimiters::default());
+        let (text, unknown) = renderer.render("${AUTHOR_NAME}");
+        assert_eq!(text, "${PACKAGE} $${X}");
+        assert!(unknown.is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Progress bar drawn on stdout when it is a terminal and verbose mode is off
+/// (the verbose log already describes every step)
+fn terminal_bar(len: u64, template: &str, verbose: bool) -> ProgressBar {
+    let bar = if !verbose && io::stdout().is_terminal() {
+        ProgressBar::with_draw_target(Some(len), ProgressDrawTarget::stdout())
+    } else {
+        ProgressBar::hidden()
+    };
+    if let Ok(style) = ProgressStyle::with_template(template) {
+        bar.set_style(style.progress_chars("=> "));
+    }
+    bar
+}
+
+/// Progress bar of a generation: bytes and files written.
+///
+/// Shared between the rendering threads.
+pub struct Progress {
+    bar: ProgressBar,
+    files: AtomicU64,
+    total_files: u64,
+}
+
+impl Progress {
+    pub fn new(total_files: u64, total_bytes: u64, verbose: bool) -> Progress {
+        let bar = terminal_bar(total_bytes, "[{bar:30}] {bytes}/{total_bytes} {msg}", verbose);
+        Progress { bar, files: AtomicU64::new(0), total_files }
+    }
+
+    /// A file of `bytes` bytes was written
+    pub fn file_done(&self, bytes: u64) {
+        let files = self.files.fetch_add(1, Ordering::Relaxed) + 1;
+        self.bar.inc(bytes);
+        self.bar.set_message(format!("{}/{} files", files, self.total_files));
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Progress bar of a git push: objects and bytes sent
+pub struct TransferProgress {
+    bar: ProgressBar,
+}
+
+impl TransferProgress {
+    pub fn new(verbose: bool) -> TransferProgress {
+        TransferProgress { bar: terminal_bar(0, "[{bar:30}] {pos}/{len} objects {msg}", verbose) }
+    }
+
+    pub fn update(&self, current: usize, total: usize, bytes: usize) {
+        self.bar.set_length(total as u64);
+        self.bar.set_position(current as u64);
+        self.bar.set_message(indicatif::HumanBytes(bytes as u64).to_string());
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
@@ -0,0 +1,214 @@
+use crate::cli::Cli;
+use crate::log::{log_success, log_verbose};
+use crate::VERSION;
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Path of the JAR manifest in the generated project
+pub const MANIFEST_PATH: &str = "src/main/resources/META-INF/MANIFEST.MF";
+/// Longest manifest line in bytes, longer values continue on the next line
+const MANIFEST_LINE_LEN: usize = 72;
+
+/// Write `src/main/resources/META-INF/MANIFEST.MF`, unless the template provides one
+pub fn write_manifest(dest: &Path, cli: &Cli) -> io::Result<()> {
+    let path = dest.join(MANIFEST_PATH);
+    if path.exists() {
+        log_verbose("MANIFEST.MF provided by the template, not generated", cli.verbose);
+        return Ok(());
+    }
+
+    let main_class = main_class(cli);
+    let created_by = format!("genj {}", VERSION);
+    let attributes = [
+        ("Manifest-Version", "1.0"),
+        ("Main-Class", main_class.as_str()),
+        ("Implementation-Title", cli.project_name.as_str()),
+        ("Implementation-Version", cli.project_version.as_str()),
+        ("Implementation-Vendor", cli.vendor_name.as_str()),
+        ("Created-By", created_by.as_str()),
+    ];
+    let content: String = attributes.iter().map(|(name, value)| manifest_line(name, value)).collect();
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, content)?;
+    log_success("MANIFEST.MF generated");
+    Ok(())
+}
+
+/// Fully qualified name of the main class
+pub fn main_class(cli: &Cli) -> String {
+    if cli.package.is_empty() {
+        cli.mainclass.clone()
+    } else {
+        format!("{}.{}", cli.package, cli.mainclass)
+    }
+}
+
+/// JAR built by Maven or Gradle, relative to the project
+pub fn jar_path(cli: &Cli, maven: bool) -> String {
+    if maven {
+        format!("target/{}-{}.jar", cli.project_name, cli.project_version)
+    } else {
+        format!("build/libs/{}-{}.jar", cli.project_name, cli.project_version)
+    }
+}
+
+/// `Name: value` line of a manifest, split into continuation lines (starting
+/// with a space) at 72 bytes, without cutting a UTF-8 character
+fn manifest_line(name: &str, value: &str) -> String {
+    let line = format!("{}: {}", name, value);
+    let mut output = String::with_capacity(line.len() + 4);
+    let mut rest = line.as_str();
+    let mut limit = MANIFEST_LINE_LEN;
+    while rest.len() > limit {
+        let mut cut = limit;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        output.push_str(&rest[..cut]);
+        output.push_str("\r\n ");
+        rest = &rest[cut..];
+        // continuation lines start with a space
+        limit = MANIFEST_LINE_LEN - 1;
+    }
+    output.push_str(rest);
+    output.push_str("\r\n");
+    output
+}
+
+/// Write `README.md` from the template metadata and the generation options,
+/// unless the template provides one
+pub fn write_readme(dest: &Path, cli: &Cli, metadata: Option<&Value>, build_tool: &str) -> io::Result<()> {
+    let path = dest.join("README.md");
+    if path.exists() {
+        log_verbose("README.md provided by the template, not generated", cli.verbose);
+        return Ok(());
+    }
+    let field = |name: &str| metadata.and_then(|m| m.get(name)).and_then(|v| v.as_str());
+
+    let mut readme = format!("# {}\n\n", cli.project_name);
+    if let Some(description) = field("description") {
+        readme.push_str(&format!("{}\n\n", description));
+    }
+    readme.push_str(&format!("- Version: {}\n", cli.project_version));
+    readme.push_str(&format!("- Author: {} <{}>\n", cli.author, cli.email));
+    if let Some(license) = cli.license {
+        readme.push_str(&format!("- License: {} (see `LICENSE`)\n", license.spdx_id()));
+    }
+    if let Some(name) = field("name") {
+        match field("version") {
+            Some(version) => readme.push_str(&format!("- Template: {} {}\n", name, version)),
+            None => readme.push_str(&format!("- Template: {}\n", name)),
+        }
+    }
+
+    let (tool, tool_version, build) = if build_tool == "maven" {
+        ("Maven", &cli.maven_version, "mvn package")
+    } else {
+        ("Gradle", &cli.gradle_version, "gradle build")
+    };
+    let jar = jar_path(cli, build_tool == "maven");
+    let main_class = main_class(cli);
+
+    readme.push_str(&format!(
+        r#"
+## Requirements
+
+- JDK {java} ({flavor})
+- {tool} {tool_version}
+
+## Setup with SDKMAN
+
+The `.sdkmanrc` file pins the JDK and {tool} versions:
+
+```bash
+sdk env install
+sdk env
+```
+
+## Build
+
+```bash
+{build}
+```
+
+## Run
+
+```bash
+java -cp {jar} {main_class}
+```
+"#,
+        java = cli.java,
+        flavor = cli.java_flavor,
+        tool = tool,
+        tool_version = tool_version,
+        build = build,
+        jar = jar,
+        main_class = main_class,
+    ));
+
+    fs::write(&path, readme)?;
+    log_success("README.md generated");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    /// Lines of a manifest entry, each at most 72 bytes
+    fn lines(entry: &str) -> Vec<&str> {
+        let lines: Vec<&str> = entry.strip_suffix("\r\n").unwrap().split("\r\n").collect();
+        assert!(lines.iter().all(|line| line.len() <= MANIFEST_LINE_LEN), "{:?}", lines);
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ')), "{:?}", lines);
+        lines
+    }
+
+    #[test]
+    fn short_manifest_line_is_kept_whole() {
+        assert_eq!(manifest_line("Main-Class", "com.example.App"), "Main-Class: com.example.App\r\n");
+    }
+
+    #[test]
+    fn long_manifest_line_continues_at_72_bytes() {
+        let value = "x".repeat(150);
+        let entry = manifest_line("Implementation-Title", &value);
+        let lines = lines(&entry);
+        assert_eq!(lines.iter().map(|l| l.len()).collect::<Vec<_>>(), [72, 72, 30]);
+        let joined: String = lines.iter().enumerate().map(|(i, l)| if i == 0 { *l } else { &l[1..] }).collect();
+        assert_eq!(joined, format!("Implementation-Title: {}", value));
+    }
+
+    #[test]
+    fn multibyte_character_is_not_cut() {
+        // `Implementation-Vendor: ` is 23 bytes: the 25th `é` straddles byte 72
+        let value = "é".repeat(40);
+        let entry = manifest_line("Implementation-Vendor", &value);
+        let lines = lines(&entry);
+        assert_eq!(lines[0].len(), 71);
+        let joined: String = lines.iter().enumerate().map(|(i, l)| if i == 0 { *l } else { &l[1..] }).collect();
+        assert_eq!(joined, format!("Implementation-Vendor: {}", value));
+    }
+
+    #[test]
+    fn readme_of_the_template_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let cli = Cli::try_parse_from(["genj", "--project_name", "Demo"]).unwrap();
+        let metadata = serde_json::json!({ "name": "basic-java", "version": "1.2", "description": "A basic project" });
+
+        write_readme(dir.path(), &cli, Some(&metadata), "maven").unwrap();
+        let readme = fs::read_to_string(dir.path().join("README.md")).unwrap();
+        assert!(readme.starts_with("# Demo\n\nA basic project\n\n"), "{}", readme);
+        assert!(readme.contains("- Template: basic-java 1.2\n"), "{}", readme);
+        assert!(readme.contains("mvn package"), "{}", readme);
+
+        fs::write(dir.path().join("README.md"), "# From the template\n").unwrap();
+        write_readme(dir.path(), &cli, Some(&metadata), "gradle").unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("README.md")).unwrap(), "# From the template\n");
+    }
+}
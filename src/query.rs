@@ -0,0 +1,387 @@
+use serde_json::Value;
+
+/// Template field a query term can target (e.g. `tag:game`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Tag,
+    Language,
+    Description,
+    Author,
+    License,
+    Version,
+    Contact,
+}
+
+impl Field {
+    /// Fields looked at by unqualified terms, in display order
+    const ALL: [Field; 8] = [
+        Field::Name,
+        Field::Tag,
+        Field::Language,
+        Field::Description,
+        Field::Author,
+        Field::License,
+        Field::Version,
+        Field::Contact,
+    ];
+
+    fn parse(key: &str) -> Option<Field> {
+        match key.to_lowercase().as_str() {
+            "name" => Some(Field::Name),
+            "tag" | "tags" => Some(Field::Tag),
+            "lang" | "language" => Some(Field::Language),
+            "desc" | "description" => Some(Field::Description),
+            "author" => Some(Field::Author),
+            "license" => Some(Field::License),
+            "version" => Some(Field::Version),
+            "contact" => Some(Field::Contact),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Field::Name => "name",
+            Field::Tag => "tags",
+            Field::Language => "language",
+            Field::Description => "description",
+            Field::Author => "author",
+            Field::License => "license",
+            Field::Version => "version",
+            Field::Contact => "contact",
+        }
+    }
+
+    /// Relevance of a match in this field: name > tag > language > description > others
+    fn weight(&self) -> u32 {
+        match self {
+            Field::Name => 100,
+            Field::Tag => 60,
+            Field::Language => 40,
+            Field::Description => 20,
+            Field::Author => 15,
+            Field::License | Field::Version | Field::Contact => 10,
+        }
+    }
+
+    /// Values of this field for a template (several for tags)
+    fn values<'a>(&self, name: &'a str, metadata: Option<&'a Value>) -> Vec<&'a str> {
+        match self {
+            Field::Name => vec![name],
+            Field::Tag => metadata
+                .and_then(|m| m.get("tags"))
+                .and_then(|v| v.as_array())
+                .map(|tags| tags.iter().filter_map(|t| t.as_str()).collect())
+                .unwrap_or_default(),
+            _ => metadata
+                .and_then(|m| m.get(self.as_str()))
+                .and_then(|v| v.as_str())
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+/// One term of a query: free text, or restricted to a field with `field:text`
+#[derive(Debug, Clone)]
+struct Term {
+    field: Option<Field>,
+    text: String,
+}
+
+/// Where a query term matched: the field value and the matched byte range in it
+#[derive(Debug, Clone)]
+pub struct FieldMatch {
+    pub field: Field,
+    pub value: String,
+    pub start: usize,
+    pub end: usize,
+    /// `false` when the match tolerated typos
+    pub exact: bool,
+}
+
+/// A template matching every term of a query
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub score: u32,
+    pub matches: Vec<FieldMatch>,
+}
+
+impl SearchHit {
+    /// Names of the matched fields, without duplicates
+    pub fn matched_fields(&self) -> Vec<&'static str> {
+        let mut fields: Vec<&'static str> = Vec::new();
+        for m in &self.matches {
+            if !fields.contains(&m.field.as_str()) {
+                fields.push(m.field.as_str());
+            }
+        }
+        fields
+    }
+}
+
+/// A parsed search query, e.g. `tag:game language:java author:delorme fps`.
+///
+/// Every term must match. Unqualified terms are looked up in every field and
+/// `"quoted text"` keeps spaces inside a term. Terms of 4 characters or more
+/// tolerate typos.
+#[derive(Debug, Clone)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Query {
+        let terms = tokenize(input)
+            .into_iter()
+            .filter_map(|token| {
+                let term = match token.split_once(':') {
+                    Some((key, text)) => match Field::parse(key) {
+                        Some(field) => Term { field: Some(field), text: text.to_lowercase() },
+                        None => Term { field: None, text: token.to_lowercase() },
+                    },
+                    None => Term { field: None, text: token.to_lowercase() },
+                };
+                (!term.text.is_empty()).then_some(term)
+            })
+            .collect();
+        Query { terms }
+    }
+
+    /// Match a template against the query, `None` when a term does not match
+    pub fn evaluate(&self, name: &str, metadata: Option<&Value>) -> Option<SearchHit> {
+        let mut score = 0;
+        let mut matches = Vec::new();
+
+        for term in &self.terms {
+            let fields: &[Field] = match &term.field {
+                Some(field) => std::slice::from_ref(field),
+                None => &Field::ALL,
+            };
+
+            let best = fields
+                .iter()
+                .flat_map(|field| {
+                    field
+                        .values(name, metadata)
+                        .into_iter()
+                        .filter_map(move |value| match_value(*field, value, &term.text))
+                })
+                .max_by_key(match_score)?;
+
+            score += match_score(&best);
+            matches.push(best);
+        }
+
+        Some(SearchHit { score, matches })
+    }
+}
+
+fn match_score(m: &FieldMatch) -> u32 {
+    let weight = m.field.weight();
+    if !m.exact {
+        return weight / 2;
+    }
+    // a term covering the whole value (e.g. the exact tag) ranks first
+    if m.start == 0 && m.end == m.value.len() {
+        weight * 3
+    } else {
+        weight * 2
+    }
+}
+
+/// Match a lowercase term against a field value: substring first, then
+/// word by word with a bounded edit distance.
+fn match_value(field: Field, value: &str, term: &str) -> Option<FieldMatch> {
+    let lower = Lowercase::new(value);
+
+    if let Some(start) = lower.text.find(term) {
+        let (start, end) = lower.original_range(start, start + term.len());
+        return Some(FieldMatch { field, value: value.to_string(), start, end, exact: true });
+    }
+
+    let max_distance = match term.chars().count() {
+        0..=3 => return None,
+        4..=6 => 1,
+        _ => 2,
+    };
+
+    words(&lower.text)
+        .into_iter()
+        .filter(|(_, word)| edit_distance(word, term) <= max_distance)
+        .min_by_key(|(_, word)| edit_distance(word, term))
+        .map(|(start, word)| {
+            let (start, end) = lower.original_range(start, start + word.len());
+            FieldMatch { field, value: value.to_string(), start, end, exact: false }
+        })
+}
+
+/// Lowercase form of a value, keeping where each character comes from: lowercasing
+/// may change the byte length of a character (`K` Kelvin sign, `İ`)
+struct Lowercase {
+    text: String,
+    /// Byte offset in `text` and in the original value of each original character
+    offsets: Vec<(usize, usize)>,
+    original_len: usize,
+}
+
+impl Lowercase {
+    fn new(value: &str) -> Lowercase {
+        let mut text = String::with_capacity(value.len());
+        let mut offsets = Vec::with_capacity(value.len());
+        for (i, c) in value.char_indices() {
+            offsets.push((text.len(), i));
+            text.extend(c.to_lowercase());
+        }
+        Lowercase { text, offsets, original_len: value.len() }
+    }
+
+    /// Range of the original value covering the characters of a range of `text`
+    fn original_range(&self, start: usize, end: usize) -> (usize, usize) {
+        let start = self.offsets.iter().rev().find(|(lower, _)| *lower <= start).map_or(0, |(_, i)| *i);
+        let end = self.offsets.iter().find(|(lower, _)| *lower >= end).map_or(self.original_len, |(_, i)| *i);
+        (start, end)
+    }
+}
+
+/// Alphanumeric words of a value with their byte offset
+fn words(value: &str) -> Vec<(usize, &str)> {
+    let mut result = Vec::new();
+    let mut start = None;
+    for (i, c) in value.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                result.push((s, &value[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        result.push((s, &value[s..]));
+    }
+    result
+}
+
+/// Edit distance counting insertions, deletions, substitutions and
+/// transpositions of adjacent characters ("gmae" is 1 away from "game")
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Split on whitespace, keeping `"quoted text"` together
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn metadata() -> Value {
+        json!({
+            "tags": ["game", "opengl"],
+            "language": "java",
+            "description": "A small game engine",
+            "author": "Jean Delorme"
+        })
+    }
+
+    #[test]
+    fn field_prefixes_restrict_terms() {
+        let query = Query::parse(r#"tag:game LANG:Java "small game" unknown:x"#);
+        let terms: Vec<(Option<Field>, &str)> = query.terms.iter().map(|t| (t.field, t.text.as_str())).collect();
+        assert_eq!(
+            terms,
+            [(Some(Field::Tag), "game"), (Some(Field::Language), "java"), (None, "small game"), (None, "unknown:x")]
+        );
+        assert!(Query::parse("tag:").terms.is_empty());
+    }
+
+    #[test]
+    fn every_term_must_match_its_field() {
+        let metadata = metadata();
+        assert!(Query::parse("tag:game language:java").evaluate("engine", Some(&metadata)).is_some());
+        assert!(Query::parse("tag:java").evaluate("engine", Some(&metadata)).is_none());
+        assert!(Query::parse("game rust").evaluate("engine", Some(&metadata)).is_none());
+    }
+
+    #[test]
+    fn heavier_fields_rank_first() {
+        let metadata = metadata();
+        let score = |name: &str| Query::parse("game").evaluate(name, Some(&metadata)).unwrap().score;
+        // whole name > name part > whole tag
+        assert!(score("game") > score("game-engine"));
+        assert!(score("game-engine") > score("engine"));
+        let description_only = Query::parse("engine").evaluate("other", Some(&metadata)).unwrap();
+        assert_eq!(description_only.matched_fields(), ["description"]);
+        assert!(description_only.score < score("engine"));
+    }
+
+    #[test]
+    fn long_terms_tolerate_typos() {
+        let metadata = metadata();
+        let hit = Query::parse("egnine").evaluate("other", Some(&metadata)).unwrap();
+        let m = &hit.matches[0];
+        assert!(!m.exact);
+        assert_eq!(&m.value[m.start..m.end], "engine");
+        assert_eq!(edit_distance("gmae", "game"), 1);
+        assert!(Query::parse("gmae").evaluate("other", Some(&metadata)).is_some());
+        assert!(Query::parse("delomre").evaluate("other", Some(&metadata)).is_some());
+        // terms under 4 characters must match exactly
+        assert!(Query::parse("gme").evaluate("other", Some(&metadata)).is_none());
+    }
+
+    #[test]
+    fn ranges_follow_non_ascii_values() {
+        // the Kelvin sign lowercases to a one-byte `k`, `İ` to two characters
+        for (value, term, matched) in [
+            ("\u{212A}elvin Été", "été", "Été"),
+            ("\u{212A}elvin Été", "kelvin", "\u{212A}elvin"),
+            ("İstanbul Kit", "kit", "Kit"),
+            ("İstanbul Kit", "i", "İ"),
+            ("Crème brûlée", "brulée", "brûlée"),
+        ] {
+            let m = match_value(Field::Description, value, term).unwrap();
+            assert_eq!(&value[m.start..m.end], matched, "{} in {}", term, value);
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use crate::archive::ArchiveFormat;
+use crate::config::UserConfig;
+use crate::fs::write_bytes;
+use crate::log::{log_info, log_success, log_verbose, log_warning};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Prefix of a `--template` value naming a registry template (`registry:name@version`)
+pub const REGISTRY_PREFIX: &str = "registry:";
+/// Environment variable overriding the configured registries (`,`-separated URLs)
+pub const REGISTRIES_ENV: &str = "GENJ_REGISTRIES";
+
+/// A template published in a registry `index.json`:
+///
+/// ```json
+/// { "templates": [ { "name": "java-game-fps", "version": "1.2.0",
+///                    "url": "java-game-fps-1.2.0.zip", "sha256": "…",
+///                    "description": "…", "tags": ["game"] } ] }
+/// ```
+///
+/// Relative URLs are resolved against the index URL. Every other field is
+/// template metadata, as in a `.template` file.
+#[derive(Debug, Clone)]
+pub struct RegistryTemplate {
+    pub name: String,
+    pub version: String,
+    pub url: String,
+    pub sha256: String,
+    pub metadata: Value,
+}
+
+impl RegistryTemplate {
+    /// Location of the verified archive in the download cache
+    pub fn cache_path(&self) -> PathBuf {
+        cache_dir().join(self.cache_name())
+    }
+
+    /// `<name>-<version>.<extension>`, keeping the archive extension of the download URL
+    fn cache_name(&self) -> String {
+        let path = self.url.split(['?', '#']).next().unwrap_or(&self.url);
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        let extension = file_name
+            .match_indices('.')
+            .map(|(pos, _)| &file_name[pos + 1..])
+            .find(|extension| ArchiveFormat::parse(extension).is_some())
+            .unwrap_or("zip");
+        format!("{}-{}.{}", self.name, self.version, extension)
+    }
+}
+
+/// Registry index URLs: `GENJ_REGISTRIES` when set, else `registries` in the user configuration
+pub fn registry_urls() -> Vec<String> {
+    let urls = match std::env::var(REGISTRIES_ENV) {
+        Ok(list) => list.split(',').map(|s| s.trim().to_string()).collect(),
+        Err(_) => UserConfig::load().get_strings("registries"),
+    };
+    urls.into_iter().filter(|u| !u.is_empty()).map(|u| index_url(&u)).collect()
+}
+
+/// Read the templates listed in a registry index
+pub fn fetch_index(url: &str) -> io::Result<Vec<RegistryTemplate>> {
+    let bytes = fetch(url)?;
+    let index: Value = serde_json::from_slice(&bytes).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Invalid registry index {}: {}", url, e))
+    })?;
+
+    let templates = index
+        .get("templates")
+        .and_then(|v| v.as_array())
+        .map(|items| items.iter().filter_map(|item| parse_template(url, item)).collect())
+        .unwrap_or_default();
+    Ok(templates)
+}
+
+/// Resolve `registry:name[@version]`: pick the newest matching version across
+/// the registries, download it into the cache, verify its sha256 and return its path.
+pub fn fetch_template(spec: &str, verbose: bool) -> io::Result<PathBuf> {
+    let urls = registry_urls();
+    if urls.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No template registry configured (set \"registries\" in ~/.config/genj/config.json)",
+        ));
+    }
+    fetch_template_from(spec, &urls, &cache_dir(), verbose)
+}
+
+fn fetch_template_from(spec: &str, urls: &[String], cache: &Path, verbose: bool) -> io::Result<PathBuf> {
+    let spec = spec.strip_prefix(REGISTRY_PREFIX).unwrap_or(spec);
+    let (name, version) = match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
+    };
+
+    let mut candidates = Vec::new();
+    for url in urls {
+        log_verbose(&format!("Reading registry index: {}", url), verbose);
+        // an unreachable registry must not hide the templates of the others
+        let templates = match fetch_index(url) {
+            Ok(templates) => templates,
+            Err(e) => {
+                log_warning(&format!("Unable to read registry {}: {}", url, e));
+                continue;
+            }
+        };
+        candidates.extend(
+            templates
+                .into_iter()
+                .filter(|t| t.name == name && version.is_none_or(|v| version_matches(&t.version, v))),
+        );
+    }
+    let template = candidates
+        .into_iter()
+        .max_by(|a, b| compare_versions(&a.version, &b.version))
+        .ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("Template not found in registries: {}", spec))
+        })?;
+
+    let cached = cache.join(template.cache_name());
+    if cached.is_file() && sha256_hex(&fs::read(&cached)?) == template.sha256 {
+        log_verbose(&format!("Using cached template: {}", cached.display()), verbose);
+        return Ok(cached);
+    }
+
+    log_info(&format!("Downloading {} {} from {}", template.name, template.version, template.url));
+    let bytes = fetch(&template.url)?;
+    let digest = sha256_hex(&bytes);
+    if digest != template.sha256 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Checksum mismatch for {} {}: expected {}, got {}",
+                template.name, template.version, template.sha256, digest
+            ),
+        ));
+    }
+    write_bytes(&cached, &bytes)?;
+    log_success(&format!("Template {} {} verified and cached", template.name, template.version));
+    Ok(cached)
+}
+
+fn parse_template(index_url: &str, item: &Value) -> Option<RegistryTemplate> {
+    let name = item.get("name")?.as_str()?.to_string();
+    let version = item.get("version")?.as_str()?.to_string();
+    // both end up in a cache file name
+    if [&name, &version].iter().any(|s| s.contains(['/', '\\']) || s.contains("..")) {
+        return None;
+    }
+    let url = resolve_url(index_url, item.get("url")?.as_str()?);
+    let sha256 = item.get("sha256")?.as_str()?.to_lowercase();
+    Some(RegistryTemplate { name, version, url, sha256, metadata: item.clone() })
+}
+
+/// Download a URL (`http://`, `https://` or `file://`)
+fn fetch(url: &str) -> io::Result<Vec<u8>> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return fs::read(path);
+    }
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| io::Error::other(format!("Unable to fetch {}: {}", url, e)))?;
+    let mut bytes = Vec::new();
+    response.into_reader().read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Registries may be configured by their base URL: `index.json` is then appended
+fn index_url(url: &str) -> String {
+    if url.ends_with(".json") {
+        url.to_string()
+    } else {
+        format!("{}/index.json", url.trim_end_matches('/'))
+    }
+}
+
+fn resolve_url(index_url: &str, url: &str) -> String {
+    if url.contains("://") {
+        return url.to_string();
+    }
+    match index_url.rfind('/') {
+        Some(pos) => format!("{}/{}", &index_url[..pos], url.trim_start_matches('/')),
+        None => url.to_string(),
+    }
+}
+
+/// `1.2` matches `1.2`, `1.2.0`, `1.2.5`… but not `1.20`
+fn version_matches(version: &str, requested: &str) -> bool {
+    version == requested || version.starts_with(&format!("{}.", requested))
+}
+
+/// Compare dotted versions numerically (`1.10` > `1.9`), falling back to text
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut left = a.split('.');
+    let mut right = b.split('.');
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(x), Some(y)) => {
+                let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    _ => x.cmp(y),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(|| Path::new(".").to_path_buf())
+        .join("genj")
+        .join("registry")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::HttpStub;
+
+    const ARCHIVE: &[u8] = b"PK\x05\x06 not much of a template";
+
+    /// A registry listing `demo` in several versions, only 1.2.5 being downloadable
+    fn registry(sha256: &str) -> HttpStub {
+        let index = serde_json::json!({ "templates": [
+            { "name": "demo", "version": "1.2.0", "url": "demo-1.2.0.tar.gz", "sha256": sha256 },
+            { "name": "demo", "version": "1.2.5", "url": "archives/demo-1.2.5.tar.gz?download=1", "sha256": sha256 },
+            { "name": "demo", "version": "1.20.0", "url": "demo-1.20.0.tar.gz", "sha256": sha256 },
+            { "name": "other", "version": "1.2.9", "url": "other.zip", "sha256": sha256 },
+        ]});
+        HttpStub::start(vec![
+            ("GET", "/index.json".to_string(), 200, index.to_string().into_bytes()),
+            ("GET", "/archives/demo-1.2.5.tar.gz?download=1".to_string(), 200, ARCHIVE.to_vec()),
+        ])
+    }
+
+    fn downloads(stub: &HttpStub) -> usize {
+        stub.requests().iter().filter(|r| r.method == "GET" && r.path.starts_with("/archives/")).count()
+    }
+
+    #[test]
+    fn newest_matching_version_is_downloaded_then_cached() {
+        let stub = registry(&sha256_hex(ARCHIVE));
+        let cache = tempfile::tempdir().unwrap();
+        // nothing listens on port 1: this registry is skipped
+        let urls = ["http://127.0.0.1:1/index.json".to_string(), index_url(&stub.url)];
+
+        let path = fetch_template_from("registry:demo@1.2", &urls, cache.path(), false).unwrap();
+        assert_eq!(path, cache.path().join("demo-1.2.5.tar.gz"));
+        assert_eq!(fs::read(&path).unwrap(), ARCHIVE);
+        assert_eq!(downloads(&stub), 1);
+
+        let again = fetch_template_from("registry:demo@1.2", &urls, cache.path(), false).unwrap();
+        assert_eq!(again, path);
+        assert_eq!(downloads(&stub), 1, "the cached archive is reused");
+    }
+
+    #[test]
+    fn checksum_mismatch_is_refused() {
+        let stub = registry(&sha256_hex(b"another archive"));
+        let cache = tempfile::tempdir().unwrap();
+        let error = fetch_template_from("demo@1.2", &[index_url(&stub.url)], cache.path(), false).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("Checksum mismatch"), "{}", error);
+        assert!(!cache.path().join("demo-1.2.5.tar.gz").exists());
+    }
+
+    #[test]
+    fn missing_template_is_not_found() {
+        let stub = registry(&sha256_hex(ARCHIVE));
+        let cache = tempfile::tempdir().unwrap();
+        let error = fetch_template_from("demo@2", &[index_url(&stub.url)], cache.path(), false).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+}
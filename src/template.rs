@@ -1,78 +1,456 @@
-use crate::fs::{is_text_path, write_bytes};
-use crate::log::{log_verbose, log_warning};
-use std::fs::{self, create_dir_all, read_to_string, copy, File};
-use std::io::{self, Read};
-use std::path::{Path, PathBuf};
+use crate::archive::{for_each_entry, ArchiveFormat, EntryKind};
+use crate::fs::{append_missing_lines, create_file_with_mode, file_mode};
+use crate::integrity::INTEGRITY_DIR;
+use crate::log::{log_error, log_verbose, log_warning};
+use std::collections::HashMap;
+use std::fmt;
+use crate::placeholder::{Delimiters, Renderer, UnknownPlaceholder};
+use crate::progress::Progress;
+use crate::text::{encoding_for_label, looks_binary, DecodedText, Eol};
+use encoding_rs::Encoding;
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use rayon::prelude::*;
+use std::fs::{create_dir_all, read_to_string, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
 use walkdir::WalkDir;
-use zip::ZipArchive;
 use serde_json::Value;
 
-/// Main entry: process template path (file or dir)
-pub fn process_template(
-    template_path: &Path,
-    dest_path: &Path,
-    replacements: &[(&str, &str)],
-    verbose: bool,
-) -> io::Result<()> {
-    if template_path.is_file() {
-        log_verbose("Template detected as ZIP file", verbose);
-        extract_zip_with_replace(template_path, dest_path, replacements, verbose)?;
-    } else if template_path.is_dir() {
-        log_verbose("Template detected as directory", verbose);
-        copy_dir_with_replace(template_path, dest_path, replacements, verbose)?;
-    } else {
-        return Err(io::Error::new(io::ErrorKind::NotFound, "Template path not found"));
+/// Folder holding the template test cases (see `genj template test`)
+pub const TEMPLATE_TESTS_DIR: &str = "tests";
+/// Suffix of files always rendered, removed from the generated file name
+pub const TEMPLATE_SUFFIX: &str = ".tmpl";
+/// Maximum number of entries of a template archive
+pub const MAX_ARCHIVE_ENTRIES: usize = 20_000;
+/// Maximum total uncompressed size of a template archive (1 GiB)
+pub const MAX_ARCHIVE_UNCOMPRESSED_SIZE: u64 = 1 << 30;
+/// Bytes read before deciding whether a file is rendered or streamed
+const TEXT_PROBE_SIZE: u64 = 8 * 1024;
+
+/// An archive entry that would escape the destination or exhaust resources.
+/// Reported as an `io::Error` of kind `InvalidData` wrapping this error.
+#[derive(Debug)]
+pub enum UnsafeArchiveError {
+    /// Entry name (or its name after substitution) outside the destination, e.g. `../../.bashrc`
+    PathTraversal(String),
+    /// Symbolic link whose target is absolute or outside the destination
+    SymlinkEscape { entry: String, target: String },
+    /// Entry written through a symbolic link created earlier by the archive
+    SymlinkTraversal(String),
+    TooManyEntries { count: usize, limit: usize },
+    TooLarge { limit: u64 },
+}
+
+impl fmt::Display for UnsafeArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnsafeArchiveError::PathTraversal(name) => {
+                write!(f, "Unsafe archive entry escapes the destination: {}", name)
+            }
+            UnsafeArchiveError::SymlinkEscape { entry, target } => {
+                write!(f, "Unsafe archive symlink {} points outside the destination: {}", entry, target)
+            }
+            UnsafeArchiveError::SymlinkTraversal(name) => {
+                write!(f, "Unsafe archive entry is written through a symlink: {}", name)
+            }
+            UnsafeArchiveError::TooManyEntries { count, limit } => {
+                write!(f, "Template archive has too many entries: {} (limit {})", count, limit)
+            }
+            UnsafeArchiveError::TooLarge { limit } => {
+                write!(f, "Template archive exceeds the uncompressed size limit of {} bytes", limit)
+            }
+        }
     }
-    Ok(())
 }
 
-fn replace_package_in_path(
-    path_str: &str,
-    replacements: &[(&str, &str)],
-    package_val: &str,
-) -> PathBuf {
-    let path_parts: Vec<&str> = path_str.split('/').collect();
-    let mut final_path = PathBuf::new();
-
-    for part in path_parts {
-        if part == "${PACKAGE}" {
-            for seg in package_val.split('.') {
-                final_path.push(seg);
+impl std::error::Error for UnsafeArchiveError {}
+
+impl From<UnsafeArchiveError> for io::Error {
+    fn from(err: UnsafeArchiveError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Per-file rules declared in `.template`:
+///
+/// - `executable`: globs of files made executable, e.g. `["build", "scripts/*.sh"]`,
+///   on top of the mode recorded in the template (archive entry or source file);
+/// - `encoding`: encoding of the text files, either one label for all of them or
+///   a map of globs to labels, e.g. `{"**/*.properties": "ISO-8859-1"}` (default UTF-8),
+///   tried in the order they are written;
+/// - `eol`: line endings of the rendered text files, `lf`, `crlf`, `native` or `preserve` (default);
+/// - `render` / `copy_verbatim`: globs of files always / never rendered. Files ending
+///   with `.tmpl` are rendered and written without the suffix. Other files are
+///   rendered when they look like text (no NUL byte, valid in their encoding);
+/// - `delimiters`: placeholder delimiters, e.g. `["@@", "@@"]` (default `${` `}`), used
+///   in paths and contents, and `file_delimiters`, a map of globs to delimiters
+///   overriding them for the contents of some files, tried in the order they are written;
+/// - `rename`: file or folder names replaced in the generated paths, e.g.
+///   `{"dot_gitignore": ".gitignore"}`, applied before placeholders are replaced.
+struct TemplateRules {
+    executable: GlobSet,
+    render: GlobSet,
+    copy_verbatim: GlobSet,
+    /// First match wins
+    encodings: Vec<EncodingRule>,
+    eol: Eol,
+    /// Renderer with the template delimiters, used for paths and by default for contents
+    renderer: Renderer,
+    /// Renderers of the `file_delimiters` globs, first match wins
+    file_renderers: Vec<(GlobMatcher, Renderer)>,
+    renames: HashMap<String, String>,
+    /// License header of the rendered Java sources (`--license-header`)
+    java_header: Option<String>,
+}
+
+/// Placeholder delimiters of the files of a template, as declared by its
+/// `delimiters` and `file_delimiters`
+pub(crate) struct TemplateDelimiters(TemplateRules);
+
+impl TemplateDelimiters {
+    pub(crate) fn new(metadata: Option<&Value>) -> TemplateDelimiters {
+        TemplateDelimiters(TemplateRules::new(metadata, &[]))
+    }
+
+    pub(crate) fn for_file(&self, relative_path: &str) -> &Delimiters {
+        self.0.file_renderer(relative_path).delimiters()
+    }
+}
+
+/// How the content of a template file is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Treatment {
+    Render,
+    Verbatim,
+    /// Rendered if it looks like text
+    Detect,
+}
+
+struct EncodingRule {
+    pattern: String,
+    /// Label as declared, reused in `.gitattributes`
+    label: String,
+    matcher: GlobMatcher,
+    encoding: &'static Encoding,
+}
+
+impl TemplateRules {
+    fn new(metadata: Option<&Value>, replacements: &[(&str, &str)]) -> TemplateRules {
+        let field = |name: &str| metadata.and_then(|m| m.get(name));
+
+        let glob_set = |name: &str| {
+            let mut builder = GlobSetBuilder::new();
+            let patterns = field(name)
+                .and_then(|v| v.as_array())
+                .map(|items| items.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>())
+                .unwrap_or_default();
+            for pattern in patterns {
+                if let Some(glob) = template_glob(pattern, name) {
+                    builder.add(glob);
+                }
+            }
+            builder.build().unwrap_or_else(|_| GlobSet::empty())
+        };
+
+        let declared: Vec<(&str, &str)> = match field("encoding") {
+            Some(Value::String(label)) => vec![("**", label.as_str())],
+            Some(Value::Object(map)) => map.iter().filter_map(|(k, v)| Some((k.as_str(), v.as_str()?))).collect(),
+            _ => Vec::new(),
+        };
+        let mut encodings = Vec::new();
+        for (pattern, label) in declared {
+            let Some(encoding) = encoding_for_label(label) else {
+                log_warning(&format!("Ignoring unknown encoding {} for {}", label, pattern));
+                continue;
+            };
+            if let Some(glob) = template_glob(pattern, "encoding") {
+                encodings.push(EncodingRule {
+                    pattern: pattern.to_string(),
+                    label: label.to_string(),
+                    matcher: glob.compile_matcher(),
+                    encoding,
+                });
+            }
+        }
+
+        let parse_delimiters = |value: &Value, context: &str| {
+            let delimiters = Delimiters::parse(value);
+            if delimiters.is_none() {
+                log_warning(&format!(
+                    "Ignoring invalid delimiters for {} (expected [\"open\", \"close\"], without '/')",
+                    context
+                ));
             }
+            delimiters
+        };
+        let delimiters = field("delimiters")
+            .and_then(|v| parse_delimiters(v, "the template"))
+            .unwrap_or_default();
+        let mut file_renderers = Vec::new();
+        if let Some(map) = field("file_delimiters").and_then(|v| v.as_object()) {
+            for (pattern, value) in map {
+                if let (Some(glob), Some(d)) = (template_glob(pattern, "file_delimiters"), parse_delimiters(value, pattern)) {
+                    file_renderers.push((glob.compile_matcher(), Renderer::new(replacements, &d)));
+                }
+            }
+        }
+
+        let eol = match field("eol").and_then(|v| v.as_str()) {
+            Some(value) => Eol::parse(value).unwrap_or_else(|| {
+                log_warning(&format!("Ignoring unknown eol policy {} (possible values: lf, crlf, native, preserve)", value));
+                Eol::default()
+            }),
+            None => Eol::default(),
+        };
+
+        let mut renames = HashMap::new();
+        if let Some(map) = field("rename").and_then(|v| v.as_object()) {
+            for (from, to) in map {
+                match to.as_str() {
+                    Some(to) if is_file_name(from) && is_file_name(to) => {
+                        renames.insert(from.to_string(), to.to_string());
+                    }
+                    _ => log_warning(&format!("Ignoring invalid rename of {} (expected a file name)", from)),
+                }
+            }
+        }
+
+        TemplateRules {
+            executable: glob_set("executable"),
+            render: glob_set("render"),
+            copy_verbatim: glob_set("copy_verbatim"),
+            encodings,
+            eol,
+            renderer: Renderer::new(replacements, &delimiters),
+            file_renderers,
+            renames,
+            java_header: None,
+        }
+    }
+
+    /// Mode of the generated file for the template file `relative_path`
+    fn mode(&self, relative_path: &str, recorded: Option<u32>) -> Option<u32> {
+        if self.executable.is_match(relative_path) {
+            Some(recorded.unwrap_or(0o644) | 0o111)
         } else {
-            let mut replaced = part.to_string();
-            for (pat, val) in replacements {
-                replaced = replaced.replace(pat, val);
+            recorded
+        }
+    }
+
+    fn encoding(&self, relative_path: &str) -> Option<&'static Encoding> {
+        self.encodings
+            .iter()
+            .find(|rule| rule.matcher.is_match(relative_path))
+            .map(|rule| rule.encoding)
+    }
+
+    /// `copy_verbatim` wins over `.tmpl`, which wins over `render`
+    fn treatment(&self, relative_path: &str) -> Treatment {
+        if self.copy_verbatim.is_match(relative_path) {
+            Treatment::Verbatim
+        } else if relative_path.ends_with(TEMPLATE_SUFFIX) || self.render.is_match(relative_path) {
+            Treatment::Render
+        } else {
+            Treatment::Detect
+        }
+    }
+
+    /// Path of the generated file: `.tmpl` templates lose their suffix
+    fn output_path<'a>(&self, relative_path: &'a str) -> &'a str {
+        match relative_path.strip_suffix(TEMPLATE_SUFFIX) {
+            Some(stripped)
+                if !stripped.is_empty()
+                    && !stripped.ends_with('/')
+                    && self.treatment(relative_path) == Treatment::Render =>
+            {
+                stripped
+            }
+            _ => relative_path,
+        }
+    }
+
+    /// Path of the generated file or folder: `.tmpl` suffix removed, names renamed,
+    /// placeholders replaced and a `${PACKAGE}` folder expanded into one folder
+    /// per package component (`${PACKAGE_PATH}` does the same inside a name).
+    fn destination_path(&self, relative_path: &str, unresolved: &mut Unresolved) -> PathBuf {
+        let output_path = self.output_path(relative_path.trim_end_matches('/'));
+        let package = self.renderer.value("PACKAGE").unwrap_or("");
+        let mut final_path = PathBuf::new();
+
+        for part in output_path.split('/') {
+            let part = self.renames.get(part).map_or(part, String::as_str);
+            if self.renderer.is_placeholder(part, "PACKAGE") {
+                final_path.extend(package.split('.'));
+            } else {
+                let (replaced, unknown) = self.renderer.render_file_name(part);
+                unresolved.add_in_path(relative_path, unknown, self.renderer.delimiters());
+                final_path.extend(replaced.split('/'));
             }
-            final_path.push(replaced);
         }
+        final_path
+    }
+
+    /// Substitute the placeholders of a text file in its own encoding and apply the
+    /// line ending policy. Returns `None` for files copied as is (binary or verbatim).
+    fn render(&self, relative_path: &str, bytes: &[u8]) -> Option<(Vec<u8>, Vec<UnknownPlaceholder>)> {
+        let encoding = self.encoding(relative_path);
+        let decoded = match self.treatment(relative_path) {
+            Treatment::Verbatim => return None,
+            Treatment::Render => DecodedText::decode_lossy(bytes, encoding),
+            Treatment::Detect => DecodedText::decode(bytes, encoding)?,
+        };
+        let (mut replaced, unknown) = self.file_renderer(relative_path).render(&decoded.text);
+        if let Some(header) = self.java_header.as_deref() {
+            if self.output_path(relative_path).ends_with(".java") && !replaced.contains("SPDX-License-Identifier") {
+                let header = if replaced.contains("\r\n") { header.replace('\n', "\r\n") } else { header.to_string() };
+                replaced.insert_str(0, &header);
+            }
+        }
+        Some((decoded.encode(&self.eol.apply(replaced)), unknown))
+    }
+
+    /// Whether a file is copied as a stream instead of being rendered, judging
+    /// from its first bytes
+    fn is_copied(&self, relative_path: &str, prefix: &[u8]) -> bool {
+        match self.treatment(relative_path) {
+            Treatment::Verbatim => true,
+            Treatment::Render => false,
+            Treatment::Detect => looks_binary(prefix, self.encoding(relative_path)),
+        }
+    }
+
+    fn file_renderer(&self, relative_path: &str) -> &Renderer {
+        self.file_renderers
+            .iter()
+            .find(|(glob, _)| glob.is_match(relative_path))
+            .map_or(&self.renderer, |(_, renderer)| renderer)
+    }
+
+    /// `.gitattributes` lines matching the declared line endings and encodings
+    fn git_attributes(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(eol) = self.eol.git_attribute() {
+            lines.push(format!("* text=auto eol={}", eol));
+        }
+        // the last matching line wins in `.gitattributes`, the first rule wins here
+        for rule in self.encodings.iter().rev() {
+            // a single encoding for the whole template is declared with `**`
+            let pattern = if rule.pattern == "**" { "*" } else { rule.pattern.as_str() };
+            lines.push(format!("{} encoding={}", pattern, rule.label));
+        }
+        lines
     }
-    final_path
 }
 
-fn is_text_bytes(buf: &[u8]) -> bool {
-    if buf.iter().any(|&b| b == 0) { return false; }
-    std::str::from_utf8(buf).is_ok()
+fn template_glob(pattern: &str, field: &str) -> Option<Glob> {
+    match GlobBuilder::new(pattern).literal_separator(true).build() {
+        Ok(glob) => Some(glob),
+        Err(e) => {
+            log_warning(&format!("Ignoring invalid {} pattern {}: {}", field, pattern, e));
+            None
+        }
+    }
+}
+
+/// Add the attributes required by the template rules to the project `.gitattributes`
+fn write_git_attributes(dest_path: &Path, rules: &TemplateRules, verbose: bool) -> io::Result<()> {
+    let lines = rules.git_attributes();
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let file = dest_path.join(".gitattributes");
+    if append_missing_lines(&file, &lines)? {
+        log_verbose(&format!("Updated {}", file.display()), verbose);
+    }
+    Ok(())
+}
+
+/// Placeholders without a value met during a generation, as `NAME in file:line`
+#[derive(Debug, Default)]
+struct Unresolved {
+    entries: Vec<String>,
 }
 
-fn extract_zip_with_replace(
-    zip_path: &Path,
+impl Unresolved {
+    fn add(&mut self, file: &str, unknown: Vec<UnknownPlaceholder>, delimiters: &Delimiters) {
+        for placeholder in unknown {
+            self.entries.push(format!("{} in {}:{}", delimiters.wrap(&placeholder.name), file, placeholder.line));
+        }
+    }
+
+    fn add_in_path(&mut self, path: &str, unknown: Vec<UnknownPlaceholder>, delimiters: &Delimiters) {
+        for placeholder in unknown {
+            self.entries.push(format!("{} in path {}", delimiters.wrap(&placeholder.name), path));
+        }
+    }
+
+    /// Warn about every unresolved placeholder; with `strict`, they are errors
+    fn report(&self, strict: bool) -> io::Result<()> {
+        for entry in &self.entries {
+            if strict {
+                log_error(&format!("Unknown placeholder {}", entry));
+            } else {
+                log_warning(&format!("Unknown placeholder {}", entry));
+            }
+        }
+        if strict && !self.entries.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} unknown placeholder(s) in the template (--strict)", self.entries.len()),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Main entry: process template path (file or dir).
+/// `java_header` is prepended to the rendered `.java` files that have no SPDX header.
+/// With `strict`, placeholders without a value are errors instead of warnings.
+pub fn process_template(
+    template_path: &Path,
     dest_path: &Path,
     replacements: &[(&str, &str)],
+    java_header: Option<&str>,
+    strict: bool,
     verbose: bool,
 ) -> io::Result<()> {
-    log_verbose(&format!("Opening ZIP file: {}", zip_path.display()), verbose);
-    let f = File::open(zip_path)?;
-    let mut archive = ZipArchive::new(f)?;
+    let mut unresolved = Unresolved::default();
+    if template_path.is_file() {
+        let format = ArchiveFormat::detect(template_path)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported template archive (expected zip, tar, tar.gz, tar.xz or tar.zst): {}", template_path.display()),
+            )
+        })?;
+        log_verbose(&format!("Template detected as {} archive", format.as_str()), verbose);
+        let mut rules = TemplateRules::new(extract_template_metadata(template_path).as_ref(), replacements);
+        rules.java_header = java_header.map(str::to_string);
+        extract_archive_with_replace(template_path, format, dest_path, &rules, &mut unresolved, verbose)?;
+        write_git_attributes(dest_path, &rules, verbose)?;
+    } else if template_path.is_dir() {
+        log_verbose("Template detected as directory", verbose);
+        let mut rules = TemplateRules::new(extract_template_metadata_from_dir(template_path).as_ref(), replacements);
+        rules.java_header = java_header.map(str::to_string);
+        copy_dir_with_replace(template_path, dest_path, &rules, &mut unresolved, verbose)?;
+        write_git_attributes(dest_path, &rules, verbose)?;
+    } else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "Template path not found"));
+    }
+    unresolved.report(strict)
+}
 
-    // collect names
-    let entry_names: Vec<String> = (0..archive.len())
-        .map(|i| archive.by_index(i).map(|e| e.name().to_string()))
-        .collect::<Result<_, _>>()?;
+/// A single path component, usable as a rename source or target
+fn is_file_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
 
-    let common_prefix = entry_names
+/// Compute the root folder shared by every entry of an archive, if any.
+/// A file at the top level (such as a flat archive with its `.genj/` folder)
+/// means there is none.
+pub(crate) fn archive_common_prefix(entry_names: &[String]) -> Option<String> {
+    entry_names
         .iter()
-        .filter_map(|name| name.find('/').map(|pos| &name[..pos + 1]))
+        .map(|name| name.find('/').map_or("", |pos| &name[..pos + 1]))
         .fold(None::<String>, |acc, p| {
             match acc {
                 None => Some(p.to_string()),
@@ -80,74 +458,282 @@ fn extract_zip_with_replace(
                 _ => Some(String::new()),
             }
         })
-        .filter(|s| !s.is_empty());
+        .filter(|s| !s.is_empty())
+}
+
+/// Whether `relative_path` is the folder `dir` or is inside it
+pub(crate) fn is_in_dir(relative_path: &str, dir: &str) -> bool {
+    relative_path == dir
+        || relative_path
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.starts_with('/') || rest.starts_with('\\'))
+}
+
+/// The template's own test cases (top-level `tests/` folder) and integrity
+/// data (`.genj/` folder) must never end up in a generated project.
+pub(crate) fn is_template_internal_path(relative_path: &str) -> bool {
+    is_in_dir(relative_path, TEMPLATE_TESTS_DIR) || is_in_dir(relative_path, INTEGRITY_DIR)
+}
+
+/// A template file written to the destination
+struct WrittenFile {
+    /// Bytes read from the template
+    size: u64,
+    /// Unknown placeholders of a rendered file, `None` for a file copied as is
+    unknown: Option<Vec<UnknownPlaceholder>>,
+}
+
+/// Write the template file `relative_path` read from `reader` to `full_path`.
+/// Text files are rendered in memory; binary and verbatim files are streamed
+/// after their first bytes. Reading more than `limit` bytes is an error.
+fn write_template_file(
+    reader: &mut dyn Read,
+    full_path: &Path,
+    relative_path: &str,
+    mode: Option<u32>,
+    rules: &TemplateRules,
+    limit: u64,
+) -> io::Result<WrittenFile> {
+    let too_large = || io::Error::from(UnsafeArchiveError::TooLarge { limit: MAX_ARCHIVE_UNCOMPRESSED_SIZE });
+    let mut reader = reader.take(limit.saturating_add(1));
+
+    let mut bytes: Vec<u8> = Vec::new();
+    (&mut reader).take(TEXT_PROBE_SIZE).read_to_end(&mut bytes)?;
+
+    if rules.is_copied(relative_path, &bytes) {
+        let mut file = create_file_with_mode(full_path, mode)?;
+        file.write_all(&bytes)?;
+        let size = bytes.len() as u64 + io::copy(&mut reader, &mut file)?;
+        if size > limit {
+            return Err(too_large());
+        }
+        return Ok(WrittenFile { size, unknown: None });
+    }
+
+    reader.read_to_end(&mut bytes)?;
+    let size = bytes.len() as u64;
+    if size > limit {
+        return Err(too_large());
+    }
+    let (output, unknown) = match rules.render(relative_path, &bytes) {
+        Some((rendered, unknown)) => (rendered, Some(unknown)),
+        None => (bytes, None),
+    };
+    create_file_with_mode(full_path, mode)?.write_all(&output)?;
+    Ok(WrittenFile { size, unknown })
+}
+
+fn extract_archive_with_replace(
+    archive_path: &Path,
+    format: ArchiveFormat,
+    dest_path: &Path,
+    rules: &TemplateRules,
+    unresolved: &mut Unresolved,
+    verbose: bool,
+) -> io::Result<()> {
+    log_verbose(&format!("Opening {} archive: {}", format.as_str(), archive_path.display()), verbose);
+
+    // collect names, rejecting absolute and `..` names before anything is written
+    let mut entry_names: Vec<String> = Vec::new();
+    let mut declared_size: u64 = 0;
+    let mut file_count: u64 = 0;
+    for_each_entry(archive_path, format, |entry| {
+        if !entry.enclosed {
+            return Err(UnsafeArchiveError::PathTraversal(entry.name).into());
+        }
+        if entry_names.len() == MAX_ARCHIVE_ENTRIES {
+            return Err(UnsafeArchiveError::TooManyEntries { count: entry_names.len() + 1, limit: MAX_ARCHIVE_ENTRIES }.into());
+        }
+        declared_size = declared_size.saturating_add(entry.size);
+        if entry.kind == EntryKind::File {
+            file_count += 1;
+        }
+        entry_names.push(entry.name);
+        Ok(true)
+    })?;
+    if declared_size > MAX_ARCHIVE_UNCOMPRESSED_SIZE {
+        return Err(UnsafeArchiveError::TooLarge { limit: MAX_ARCHIVE_UNCOMPRESSED_SIZE }.into());
+    }
+
+    let common_prefix = archive_common_prefix(&entry_names);
 
     if let Some(prefix) = common_prefix.as_ref() {
         log_verbose(&format!("Detected common root prefix: {}", prefix), verbose);
     }
 
-    for i in 0..archive.len() {
-        let mut entry = archive.by_index(i)?;
-        let raw_name = entry.name().to_string();
+    // sizes in the archive headers can lie: count what is actually decompressed
+    let mut remaining = MAX_ARCHIVE_UNCOMPRESSED_SIZE;
+    let progress = Progress::new(file_count, declared_size, verbose);
+    let mut symlinks: Vec<(PathBuf, String, String)> = Vec::new();
+
+    for_each_entry(archive_path, format, |entry| {
+        let raw_name = entry.name;
 
         let relative_path = if let Some(prefix) = common_prefix.as_ref() {
             raw_name.as_str().strip_prefix(prefix).unwrap_or(raw_name.as_str())
         } else {
             raw_name.as_str()
         };
+        let relative_path = relative_path.trim_start_matches("./");
 
-        let package_val = replacements
-            .iter()
-            .find(|(k, _)| *k == "${PACKAGE}")
-            .map(|(_, v)| *v)
-            .unwrap_or("");
+        if is_template_internal_path(relative_path) {
+            return Ok(true);
+        }
+
+        // substituted values (e.g. a package like `..`) must not move the entry out either
+        let outpath = rules.destination_path(relative_path, unresolved);
+        if !outpath.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+            return Err(UnsafeArchiveError::PathTraversal(raw_name).into());
+        }
+        let full_path = dest_path.join(&outpath);
+        // nothing is written through a link, the link itself excepted
+        let checked = if matches!(entry.kind, EntryKind::Symlink(_)) { outpath.parent() } else { Some(outpath.as_path()) };
+        check_no_symlink_in_path(dest_path, checked.unwrap_or(Path::new("")), &raw_name)?;
 
-        let outpath = replace_package_in_path(relative_path, replacements, package_val);
-        let full_path = dest_path.join(outpath);
+        match entry.kind {
+            EntryKind::Dir => {
+                create_dir_all(&full_path)?;
+                log_verbose(&format!("Created directory: {}", full_path.display()), verbose);
+                return Ok(true);
+            }
+            EntryKind::Symlink(target) => {
+                if let Some(parent) = full_path.parent() {
+                    create_dir_all(parent)?;
+                }
+                create_symlink_in(&outpath, &target, dest_path, &raw_name)?;
+                log_verbose(&format!("Created symlink: {} -> {}", full_path.display(), target), verbose);
+                symlinks.push((full_path, raw_name, target));
+                return Ok(true);
+            }
+            EntryKind::Other => {
+                log_warning(&format!("Skipped unsupported archive entry: {}", raw_name));
+                return Ok(true);
+            }
+            EntryKind::File => {}
+        }
 
-        if raw_name.ends_with('/') {
-            create_dir_all(&full_path)?;
-            log_verbose(&format!("Created directory: {}", full_path.display()), verbose);
-            continue;
+        let mode = rules.mode(relative_path, entry.mode);
+        let written = write_template_file(entry.reader, &full_path, relative_path, mode, rules, remaining)?;
+        remaining -= written.size;
+        progress.file_done(written.size);
+        match written.unknown {
+            Some(unknown) => {
+                unresolved.add(relative_path, unknown, rules.file_renderer(relative_path).delimiters());
+                log_verbose(&format!("Extracted and replaced: {}", raw_name), verbose);
+            }
+            None => log_verbose(&format!("Copied binary file: {}", full_path.display()), verbose),
         }
+        Ok(true)
+    })?;
+    progress.finish();
 
-        if let Some(parent) = full_path.parent() {
-            create_dir_all(parent)?;
+    // a link checked when it was created can be redirected by a later one
+    // (`z -> d/x/..`, then `d/x -> ..`): resolve them all once extracted
+    let root = dest_path.canonicalize()?;
+    for (link, entry, target) in symlinks {
+        if let Ok(resolved) = link.canonicalize() {
+            if !resolved.starts_with(&root) {
+                std::fs::remove_file(&link)?;
+                return Err(UnsafeArchiveError::SymlinkEscape { entry, target }.into());
+            }
         }
+    }
+    Ok(())
+}
 
-        let mut bytes: Vec<u8> = Vec::new();
-        entry.read_to_end(&mut bytes)?;
+/// Refuse `relative` (below `dest_path`) if one of its components is a symbolic
+/// link already on disk: `create_dir_all` or `File::create` would follow it,
+/// out of the destination when it was chained with other links.
+fn check_no_symlink_in_path(dest_path: &Path, relative: &Path, entry_name: &str) -> io::Result<()> {
+    let mut current = dest_path.to_path_buf();
+    for component in relative.components() {
+        current.push(component);
+        match current.symlink_metadata() {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                return Err(UnsafeArchiveError::SymlinkTraversal(entry_name.to_string()).into());
+            }
+            Ok(_) => {}
+            // nothing exists below
+            Err(_) => break,
+        }
+    }
+    Ok(())
+}
 
-        if !is_text_bytes(&bytes) {
-            write_bytes(&full_path, &bytes)?;
-            log_verbose(&format!("Copied binary file: {}", full_path.display()), verbose);
-            continue;
+/// Create the symlink `link` (relative to `dest_path`) pointing to `target`,
+/// which must be relative and stay inside `dest_path` without going through
+/// another link.
+fn create_symlink_in(link: &Path, target: &str, dest_path: &Path, entry_name: &str) -> io::Result<()> {
+    let escape = || UnsafeArchiveError::SymlinkEscape { entry: entry_name.to_string(), target: target.to_string() };
+
+    let target_path = Path::new(target);
+    let mut resolved = PathBuf::new();
+    let joined = link.parent().unwrap_or(Path::new("")).join(target_path);
+    let components: Vec<Component> = joined.components().collect();
+    for (index, component) in components.iter().enumerate() {
+        match component {
+            Component::Normal(part) => {
+                resolved.push(part);
+                let last = index + 1 == components.len();
+                if !last && dest_path.join(&resolved).symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+                    return Err(escape().into());
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return Err(escape().into());
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return Err(escape().into()),
         }
+    }
 
-        let content = String::from_utf8(bytes).unwrap_or_default();
-        let replaced = replacements.iter().fold(content, |acc, (pat, val)| acc.replace(pat, val));
-        std::fs::write(full_path, replaced)?;
-        log_verbose(&format!("Extracted and replaced: {}", raw_name), verbose);
+    #[cfg(unix)]
+    {
+        let full_path = dest_path.join(link);
+        if full_path.symlink_metadata().is_ok() {
+            std::fs::remove_file(&full_path)?;
+        }
+        std::os::unix::fs::symlink(target_path, full_path)
+    }
+    #[cfg(not(unix))]
+    {
+        log_warning(&format!("Symlinks are not supported on this platform, skipped: {}", entry_name));
+        let _ = dest_path;
+        Ok(())
     }
+}
 
-    Ok(())
+/// A file of a directory template, written by [`copy_dir_with_replace`]
+struct TemplateFile {
+    source: PathBuf,
+    relative_path: String,
+    dest: PathBuf,
+    size: u64,
 }
 
 fn copy_dir_with_replace(
     src_dir: &Path,
     dest_dir: &Path,
-    replacements: &[(&str, &str)],
+    rules: &TemplateRules,
+    unresolved: &mut Unresolved,
     verbose: bool,
 ) -> io::Result<()> {
     log_verbose(&format!("Scanning source directory: {}", src_dir.display()), verbose);
 
-    let package_val = replacements.iter().find(|(k, _)| *k == "${PACKAGE}").map(|(_, v)| *v).unwrap_or("");
+    let walker = WalkDir::new(src_dir).into_iter().filter_entry(|e| {
+        e.path()
+            .strip_prefix(src_dir)
+            .map_or(true, |rel| !is_template_internal_path(&rel.to_string_lossy()))
+    });
 
-    for entry in WalkDir::new(src_dir).into_iter().filter_map(Result::ok) {
+    // directories and paths are resolved in walk order, files are then written in parallel
+    let mut files: Vec<TemplateFile> = Vec::new();
+    for entry in walker.filter_map(Result::ok) {
         let rel = entry.path().strip_prefix(src_dir).unwrap();
-        let rel_str = rel.to_string_lossy();
-        let new_path = replace_package_in_path(&rel_str, replacements, package_val);
-        let full_dest_path = dest_dir.join(new_path);
+        let rel_slash = rel.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        let full_dest_path = dest_dir.join(rules.destination_path(&rel_slash, unresolved));
 
         if entry.file_type().is_dir() {
             create_dir_all(&full_dest_path)?;
@@ -156,29 +742,39 @@ fn copy_dir_with_replace(
         }
 
         if entry.file_type().is_file() {
-            match is_text_path(entry.path()) {
-                Ok(true) => {
-                    if let Some(parent) = full_dest_path.parent() {
-                        create_dir_all(parent)?;
-                    }
-                    let content = read_to_string(entry.path())?;
-                    let replaced = replacements.iter().fold(content, |acc, (pat, val)| acc.replace(pat, val));
-                    std::fs::write(&full_dest_path, replaced)?;
-                    log_verbose(&format!("Copied and replaced: {}", full_dest_path.display()), verbose);
-                }
-                Ok(false) => {
-                    if let Some(parent) = full_dest_path.parent() {
-                        create_dir_all(parent)?;
-                    }
-                    match copy(entry.path(), &full_dest_path) {
-                        Ok(_) => { log_verbose(&format!("Copied binary file: {}", full_dest_path.display()), verbose); }
-                        Err(err) => { log_warning(&format!("Failed to copy binary file {}: {}", entry.path().display(), err)); }
-                    }
-                }
+            let size = entry.metadata().map_or(0, |m| m.len());
+            files.push(TemplateFile { source: entry.into_path(), relative_path: rel_slash, dest: full_dest_path, size });
+        }
+    }
+
+    let total_size = files.iter().map(|f| f.size).sum();
+    let progress = Progress::new(files.len() as u64, total_size, verbose);
+    let results: Vec<io::Result<Option<Vec<UnknownPlaceholder>>>> = files
+        .par_iter()
+        .map(|file| {
+            let mut source = match File::open(&file.source) {
+                Ok(source) => source,
                 Err(err) => {
-                    log_warning(&format!("Error reading file {}: {}", entry.path().display(), err));
+                    log_warning(&format!("Error reading file {}: {}", file.source.display(), err));
+                    return Ok(None);
                 }
+            };
+            let mode = rules.mode(&file.relative_path, file_mode(&file.source));
+            let written = write_template_file(&mut source, &file.dest, &file.relative_path, mode, rules, u64::MAX)?;
+            progress.file_done(written.size);
+            match written.unknown {
+                Some(_) => log_verbose(&format!("Copied and replaced: {}", file.dest.display()), verbose),
+                None => log_verbose(&format!("Copied binary file: {}", file.dest.display()), verbose),
             }
+            Ok(written.unknown)
+        })
+        .collect();
+    progress.finish();
+
+    // unknown placeholders are reported in walk order, whatever the thread that found them
+    for (file, result) in files.iter().zip(results) {
+        if let Some(unknown) = result? {
+            unresolved.add(&file.relative_path, unknown, rules.file_renderer(&file.relative_path).delimiters());
         }
     }
 
@@ -186,41 +782,29 @@ fn copy_dir_with_replace(
     Ok(())
 }
 
-/// Extract .template metadata from a ZIP file
-fn extract_template_metadata(zip_path: &Path) -> Option<Value> {
-    let f = match File::open(zip_path) {
-        Ok(file) => file,
-        Err(_) => return None,
-    };
-    
-    let mut archive = match ZipArchive::new(f) {
-        Ok(a) => a,
-        Err(_) => return None,
-    };
-    
+/// Extract .template metadata from a template archive
+pub(crate) fn extract_template_metadata(archive_path: &Path) -> Option<Value> {
+    let format = ArchiveFormat::detect(archive_path).ok()??;
+
     // Find .template file in the archive
-    for i in 0..archive.len() {
-        let mut entry = match archive.by_index(i) {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
-        
-        if entry.name() == ".template" || entry.name().ends_with("/.template") {
-            let mut content = String::new();
-            if entry.read_to_string(&mut content).is_ok() {
-                if let Ok(metadata) = serde_json::from_str::<Value>(&content) {
-                    return Some(metadata);
-                }
-            }
-            break;
+    let mut metadata = None;
+    let _ = for_each_entry(archive_path, format, |entry| {
+        if entry.kind != EntryKind::File
+            || !(entry.name == ".template" || entry.name.ends_with("/.template"))
+        {
+            return Ok(true);
         }
-    }
-    
-    None
+        let mut content = String::new();
+        if entry.reader.read_to_string(&mut content).is_ok() {
+            metadata = serde_json::from_str::<Value>(&content).ok();
+        }
+        Ok(false)
+    });
+    metadata
 }
 
 /// Extract .template metadata from a directory
-fn extract_template_metadata_from_dir(dir_path: &Path) -> Option<Value> {
+pub(crate) fn extract_template_metadata_from_dir(dir_path: &Path) -> Option<Value> {
     let template_file = dir_path.join(".template");
     if template_file.exists() {
         if let Ok(content) = read_to_string(&template_file) {
@@ -232,262 +816,181 @@ fn extract_template_metadata_from_dir(dir_path: &Path) -> Option<Value> {
     None
 }
 
-/// List available templates from system and user directories with metadata
-pub fn list_available_templates() {
-    let system_path = Path::new("/usr/share/genj/templates");
-    let home_dir = dirs::home_dir().map(|h| h.join(".genj"));
-    
-    println!("=== Available Templates ===\n");
-    
-    // List system templates
-    println!("📦 System templates (/usr/share/genj/templates):");
-    list_templates_in_dir_with_metadata(system_path, "/usr/share/genj/templates");
-    
-    // List user templates
-    if let Some(user_path) = home_dir {
-        println!("\n👤 User templates (~/.genj):");
-        let home_path_str = format!("{}/.genj", dirs::home_dir().map(|h| h.display().to_string()).unwrap_or_else(|| "~".to_string()));
-        list_templates_in_dir_with_metadata(&user_path, &home_path_str);
-    }
-    
-    println!("\n💡 Usage: genj -t <template_name_or_path> -d <destination> [options]");
-    println!("   Or: genj -t /usr/share/genj/templates/basic-java.zip -d ./out -n MyProject");
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-/// Search for templates matching a search term in name and metadata
-pub fn search_templates(search_term: &str) {
-    let system_path = Path::new("/usr/share/genj/templates");
-    let home_dir = dirs::home_dir().map(|h| h.join(".genj"));
-    
-    let search_lower = search_term.to_lowercase();
-    let mut results_found = false;
-    
-    println!("=== Search Results for: '{}' ===\n", search_term);
-    
-    // Search system templates
-    if system_path.exists() {
-        println!("📦 System templates (/usr/share/genj/templates):");
-        if search_templates_in_dir(&system_path, &search_lower, "/usr/share/genj/templates") {
-            results_found = true;
-        }
-    }
-    
-    // Search user templates
-    if let Some(user_path) = home_dir {
-        if user_path.exists() {
-            println!("👤 User templates (~/.genj):");
-            let home_path_str = format!("{}/.genj", dirs::home_dir().map(|h| h.display().to_string()).unwrap_or_else(|| "~".to_string()));
-            if search_templates_in_dir(&user_path, &search_lower, &home_path_str) {
-                results_found = true;
-            }
-        }
+    fn rules(template: &str) -> TemplateRules {
+        let metadata: Value = serde_json::from_str(template).unwrap();
+        TemplateRules::new(Some(&metadata), &[("${PROJECT_NAME}", "Demo")])
     }
-    
-    if !results_found {
-        println!("  No templates found matching '{}'", search_term);
+
+    #[test]
+    fn encoding_rules_apply_in_declared_order() {
+        let rules = rules(r#"{"encoding": {"**/*.properties": "ISO-8859-1", "**": "UTF-8"}}"#);
+        assert_eq!(rules.encoding("src/main/resources/messages.properties"), encoding_for_label("ISO-8859-1"));
+        assert_eq!(rules.encoding("src/main/java/App.java"), Some(encoding_rs::UTF_8));
+        // git applies the last matching line
+        assert_eq!(rules.git_attributes(), ["* encoding=UTF-8", "**/*.properties encoding=ISO-8859-1"]);
     }
-    
-    println!("\n💡 Usage: genj -t <template_name_or_path> -d <destination> [options]");
-    println!("   Or: genj -t /usr/share/genj/templates/basic-java.zip -d ./out -n MyProject");
-}
 
-/// Search templates in a specific directory
-fn search_templates_in_dir(path: &Path, search_term: &str, _display_path: &str) -> bool {
-    if !path.exists() {
-        return false;
-    }
-    
-    let mut results_found = false;
-    
-    match fs::read_dir(path) {
-        Ok(entries) => {
-            let mut templates: Vec<_> = entries
-                .filter_map(|e| e.ok())
-                .filter_map(|e| {
-                    let path = e.path();
-                    let name = path.file_name()?.to_string_lossy().to_string();
-                    if path.is_file() && name.ends_with(".zip") {
-                        Some((name, path, true))
-                    } else if path.is_dir() {
-                        Some((format!("{}/", name), path, false))
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            
-            templates.sort_by(|a, b| a.0.cmp(&b.0));
-            
-            for (name, path, is_file) in templates {
-                let metadata = if is_file {
-                    extract_template_metadata(&path)
-                } else {
-                    extract_template_metadata_from_dir(&path)
-                };
-                
-                // Check if name matches
-                let name_matches = name.to_lowercase().contains(search_term);
-                
-                // Check if any metadata field matches
-                let mut metadata_matches = false;
-                if let Some(metadata) = &metadata {
-                    let searchable_fields = vec![
-                        metadata.get("description").and_then(|v| v.as_str()),
-                        metadata.get("language").and_then(|v| v.as_str()),
-                        metadata.get("author").and_then(|v| v.as_str()),
-                        metadata.get("version").and_then(|v| v.as_str()),
-                        metadata.get("contact").and_then(|v| v.as_str()),
-                        metadata.get("license").and_then(|v| v.as_str()),
-                    ];
-                    
-                    for field in searchable_fields {
-                        if let Some(value) = field {
-                            if value.to_lowercase().contains(search_term) {
-                                metadata_matches = true;
-                                break;
-                            }
-                        }
-                    }
-                    
-                    // Check tags array
-                    if !metadata_matches {
-                        if let Some(tags) = metadata.get("tags").and_then(|v| v.as_array()) {
-                            for tag in tags {
-                                if let Some(tag_str) = tag.as_str() {
-                                    if tag_str.to_lowercase().contains(search_term) {
-                                        metadata_matches = true;
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                
-                // Display matching templates
-                if name_matches || metadata_matches {
-                    println!("\n  📋 Template: {}", name);
-                    
-                    if let Some(metadata) = metadata {
-                        // Display all metadata fields with bold labels
-                        if let Some(description) = metadata.get("description").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mDescription:\x1b[0m {}", description);
-                        }
-                        if let Some(language) = metadata.get("language").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mLanguage:\x1b[0m {}", language);
-                        }
-                        if let Some(version) = metadata.get("version").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mVersion:\x1b[0m {}", version);
-                        }
-                        if let Some(author) = metadata.get("author").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mAuthor:\x1b[0m {}", author);
-                        }
-                        if let Some(contact) = metadata.get("contact").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mContact:\x1b[0m {}", contact);
-                        }
-                        if let Some(license) = metadata.get("license").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mLicense:\x1b[0m {}", license);
-                        }
-                        if let Some(tags) = metadata.get("tags").and_then(|v| v.as_array()) {
-                            let tag_strs: Vec<_> = tags.iter()
-                                .filter_map(|t| t.as_str())
-                                .collect();
-                            if !tag_strs.is_empty() {
-                                println!("     \x1b[1mTags:\x1b[0m {}", tag_strs.join(", "));
-                            }
-                        }
-                        if let Some(created_at) = metadata.get("created_at").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mCreated:\x1b[0m {}", created_at);
-                        }
-                    } else {
-                        println!("     (No metadata available)");
-                    }
-                    
-                    results_found = true;
-                }
-            }
+    #[test]
+    fn file_delimiters_apply_in_declared_order() {
+        let rules = rules(r#"{"file_delimiters": {"scripts/*.sh": ["@@", "@@"], "**": ["<%", "%>"]}}"#);
+        assert_eq!(rules.file_renderer("scripts/run.sh").render("@@PROJECT_NAME@@ <%PROJECT_NAME%>").0, "Demo <%PROJECT_NAME%>");
+        assert_eq!(rules.file_renderer("src/App.java").render("@@PROJECT_NAME@@ <%PROJECT_NAME%>").0, "@@PROJECT_NAME@@ Demo");
+    }
+
+    #[test]
+    fn render_and_copy_verbatim_rules() {
+        let rules = rules(r#"{"render": ["data/*.bin"], "copy_verbatim": ["docs/**"]}"#);
+        // copy_verbatim wins over `.tmpl`, which wins over the content detection
+        assert_eq!(rules.treatment("docs/notes.md.tmpl"), Treatment::Verbatim);
+        assert_eq!(rules.output_path("docs/notes.md.tmpl"), "docs/notes.md.tmpl");
+        assert_eq!(rules.treatment("src/App.java.tmpl"), Treatment::Render);
+        assert_eq!(rules.output_path("src/App.java.tmpl"), "src/App.java");
+        assert_eq!(rules.output_path("src/.tmpl"), "src/.tmpl");
+        assert_eq!(rules.treatment("src/App.java"), Treatment::Detect);
+
+        assert!(rules.render("docs/guide.md", b"${PROJECT_NAME}").is_none());
+        assert!(rules.is_copied("docs/guide.md", b"${PROJECT_NAME}"));
+        // rendered even though the NUL byte looks binary
+        assert!(!rules.is_copied("data/blob.bin", b"\0${PROJECT_NAME}"));
+        assert_eq!(rules.render("data/blob.bin", b"\0${PROJECT_NAME}").unwrap().0, b"\0Demo");
+        assert!(rules.is_copied("data/blob.dat", b"\0${PROJECT_NAME}"));
+    }
+
+    #[test]
+    fn tmpl_files_are_rendered_without_their_suffix() {
+        let template = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        std::fs::write(template.path().join(".template"), r#"{"copy_verbatim": ["docs/**"]}"#).unwrap();
+        std::fs::write(template.path().join("README.md.tmpl"), "# ${PROJECT_NAME}\n").unwrap();
+        std::fs::create_dir(template.path().join("docs")).unwrap();
+        std::fs::write(template.path().join("docs/usage.md.tmpl"), "${PROJECT_NAME}\n").unwrap();
+
+        let project = dest.path().join("Demo");
+        process_template(template.path(), &project, &[("${PROJECT_NAME}", "Demo")], None, true, false).unwrap();
+        assert_eq!(std::fs::read_to_string(project.join("README.md")).unwrap(), "# Demo\n");
+        assert!(!project.join("README.md.tmpl").exists());
+        assert_eq!(std::fs::read_to_string(project.join("docs/usage.md.tmpl")).unwrap(), "${PROJECT_NAME}\n");
+    }
+
+    #[test]
+    fn strict_generation_fails_on_unknown_placeholders() {
+        let template = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        std::fs::write(template.path().join("a.txt"), "${PROJECT_NAME}\n${UNKNOWN}\n").unwrap();
+
+        let project = dest.path().join("strict");
+        let error = process_template(template.path(), &project, &[("${PROJECT_NAME}", "Demo")], None, true, false).unwrap_err();
+        assert!(error.to_string().contains("1 unknown placeholder(s)"), "{}", error);
+
+        let project = dest.path().join("lenient");
+        process_template(template.path(), &project, &[("${PROJECT_NAME}", "Demo")], None, false, false).unwrap();
+        assert_eq!(std::fs::read_to_string(project.join("a.txt")).unwrap(), "Demo\n${UNKNOWN}\n");
+
+        let rules = rules(r#"{"delimiters": ["@@", "@@"]}"#);
+        let (_, unknown) = rules.render("a.txt", b"@@PROJECT_NAME@@\n@@UNKNOWN@@\n").unwrap();
+        let mut unresolved = Unresolved::default();
+        unresolved.add("a.txt", unknown, rules.renderer.delimiters());
+        assert_eq!(unresolved.entries, ["@@UNKNOWN@@ in a.txt:2"]);
+    }
+
+    #[test]
+    fn package_expands_into_folders() {
+        let replacements = [("${PACKAGE}", "com.example"), ("${PACKAGE_PATH}", "com/example"), ("${MAINCLASS}", "App")];
+        let rules = TemplateRules::new(None, &replacements);
+        let mut unresolved = Unresolved::default();
+        for path in ["src/${PACKAGE}/App.java", "src/__package__/App.java", "src/_PACKAGE_/App.java"] {
+            assert_eq!(rules.destination_path(path, &mut unresolved), Path::new("src/com/example/App.java"));
         }
-        Err(_) => {}
+        assert_eq!(rules.destination_path("docs/${PACKAGE_PATH}-docs", &mut unresolved), Path::new("docs/com/example-docs"));
+        assert_eq!(rules.destination_path("src/_PACKAGE_PATH_/_MAINCLASS_.java", &mut unresolved), Path::new("src/com/example/App.java"));
+        assert!(unresolved.entries.is_empty());
     }
-    
-    results_found
-}
 
-fn list_templates_in_dir_with_metadata(path: &Path, _display_path: &str) {
-    if !path.exists() {
-        println!("  (No templates found - directory does not exist)");
-        return;
-    }
-    
-    match fs::read_dir(path) {
-        Ok(entries) => {
-            let mut templates: Vec<_> = entries
-                .filter_map(|e| e.ok())
-                .filter_map(|e| {
-                    let path = e.path();
-                    let name = path.file_name()?.to_string_lossy().to_string();
-                    if path.is_file() && name.ends_with(".zip") {
-                        Some((name, path, true))
-                    } else if path.is_dir() {
-                        Some((format!("{}/", name), path, false))
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            
-            templates.sort_by(|a, b| a.0.cmp(&b.0));
-            
-            if templates.is_empty() {
-                println!("  (No templates found)");
+    #[test]
+    fn latin1_file_before_catch_all_is_rendered() {
+        let template = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        std::fs::write(
+            template.path().join(".template"),
+            r#"{"encoding": {"**/*.properties": "ISO-8859-1", "**": "UTF-8"}}"#,
+        )
+        .unwrap();
+        std::fs::write(template.path().join("messages.properties"), b"title=\xe9t\xe9 ${PROJECT_NAME}\n").unwrap();
+
+        let project = dest.path().join("Demo");
+        process_template(template.path(), &project, &[("${PROJECT_NAME}", "Demo")], None, true, false).unwrap();
+        assert_eq!(std::fs::read(project.join("messages.properties")).unwrap(), b"title=\xe9t\xe9 Demo\n");
+    }
+
+    /// Entries of a test tar: (name, link target or file content, is a link).
+    /// Names are written raw, the `tar` builder refusing `..` and absolute ones.
+    fn write_tar(path: &Path, entries: &[(&str, &str, bool)]) {
+        let mut builder = tar::Builder::new(std::fs::File::create(path).unwrap());
+        for (name, data, link) in entries {
+            let mut header = tar::Header::new_ustar();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_mode(0o644);
+            if *link {
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_link_name(data).unwrap();
+                header.set_size(0);
+                header.set_cksum();
+                builder.append(&header, io::empty()).unwrap();
             } else {
-                for (name, path, is_file) in templates {
-                    let metadata = if is_file {
-                        extract_template_metadata(&path)
-                    } else {
-                        extract_template_metadata_from_dir(&path)
-                    };
-                    
-                    println!("\n  📋 Template: {}", name);
-                    
-                    if let Some(metadata) = metadata {
-                        // Display all metadata fields with bold labels
-                        if let Some(description) = metadata.get("description").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mDescription:\x1b[0m {}", description);
-                        }
-                        if let Some(language) = metadata.get("language").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mLanguage:\x1b[0m {}", language);
-                        }
-                        if let Some(version) = metadata.get("version").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mVersion:\x1b[0m {}", version);
-                        }
-                        if let Some(author) = metadata.get("author").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mAuthor:\x1b[0m {}", author);
-                        }
-                        if let Some(contact) = metadata.get("contact").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mContact:\x1b[0m {}", contact);
-                        }
-                        if let Some(license) = metadata.get("license").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mLicense:\x1b[0m {}", license);
-                        }
-                        if let Some(tags) = metadata.get("tags").and_then(|v| v.as_array()) {
-                            let tag_strs: Vec<_> = tags.iter()
-                                .filter_map(|t| t.as_str())
-                                .collect();
-                            if !tag_strs.is_empty() {
-                                println!("     \x1b[1mTags:\x1b[0m {}", tag_strs.join(", "));
-                            }
-                        }
-                        if let Some(created_at) = metadata.get("created_at").and_then(|v| v.as_str()) {
-                            println!("     \x1b[1mCreated:\x1b[0m {}", created_at);
-                        }
-                    } else {
-                        println!("     (No metadata available)");
-                    }
-                }
-                println!();
+                header.set_size(data.len() as u64);
+                header.set_cksum();
+                builder.append(&header, data.as_bytes()).unwrap();
             }
         }
-        Err(e) => println!("  Error reading directory: {}", e),
+        builder.finish().unwrap();
+    }
+
+    fn extract(entries: &[(&str, &str, bool)]) -> (tempfile::TempDir, io::Result<()>) {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("template.tar");
+        write_tar(&archive, entries);
+        let result = process_template(&archive, &dir.path().join("out/P"), &[], None, false, false);
+        (dir, result)
+    }
+
+    #[test]
+    fn chained_symlinks_cannot_escape() {
+        let (dir, result) = extract(&[
+            ("tpl/d/link", "..", true),
+            ("tpl/d/link/l2", "..", true),
+            ("tpl/d/link/l2/PWNED.txt", "pwned", false),
+        ]);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(!dir.path().join("out/PWNED.txt").exists());
+        assert!(!dir.path().join("out/P/PWNED.txt").exists());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn symlink_redirected_by_a_later_one_is_refused() {
+        let (dir, result) = extract(&[("tpl/z", "d/x/..", true), ("tpl/d/x", "..", true), ("tpl/a.txt", "a", false)]);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(dir.path().join("out/P/z").symlink_metadata().is_err());
+    }
+
+    #[test]
+    fn symlinks_inside_the_destination_are_extracted() {
+        let (dir, result) = extract(&[("tpl/docs/README.md", "read me", false), ("tpl/README.md", "docs/README.md", true)]);
+        result.unwrap();
+        assert_eq!(std::fs::read_to_string(dir.path().join("out/P/README.md")).unwrap(), "read me");
+    }
+
+    #[test]
+    fn parent_and_absolute_entry_names_are_refused() {
+        for name in ["../evil.txt", "tpl/../../evil.txt", "/tmp/genj-evil.txt"] {
+            let (dir, result) = extract(&[("tpl/a.txt", "a", false), (name, "evil", false)]);
+            assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData, "{}", name);
+            assert!(!dir.path().join("evil.txt").exists());
+            assert!(!dir.path().join("out/evil.txt").exists());
+        }
+        assert!(!Path::new("/tmp/genj-evil.txt").exists());
+    }
+}
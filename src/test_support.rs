@@ -0,0 +1,81 @@
+//! Local HTTP server standing for registries and forges in the tests
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+/// A request received by [`HttpStub`]
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercased
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Answer of a route: `(method, path, status, body)`; other requests get a 404
+pub(crate) type Route = (&'static str, String, u16, Vec<u8>);
+
+/// HTTP/1.1 server on a free local port, answering one request per connection
+pub(crate) struct HttpStub {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl HttpStub {
+    pub fn start(routes: Vec<Route>) -> HttpStub {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&requests);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                answer(stream, &routes, &received);
+            }
+        });
+        HttpStub { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Read one request, record it then send the answer of its route
+fn answer(mut stream: TcpStream, routes: &[Route], received: &Mutex<Vec<Request>>) -> Option<()> {
+    let mut reader = BufReader::new(stream.try_clone().ok()?);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let (method, path) = (parts.next()?.to_string(), parts.next()?.to_string());
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).ok()?;
+        match line.trim_end().split_once(':') {
+            Some((name, value)) => headers.push((name.trim().to_lowercase(), value.trim().to_string())),
+            None => break,
+        }
+    }
+    let length = headers.iter().find(|(n, _)| n == "content-length").and_then(|(_, v)| v.parse().ok()).unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+
+    let (status, content) = routes
+        .iter()
+        .find(|(m, p, _, _)| *m == method && *p == path)
+        .map(|(_, _, status, content)| (*status, content.clone()))
+        .unwrap_or((404, br#"{"message": "Not Found"}"#.to_vec()));
+    received.lock().unwrap().push(Request { method, path, headers, body: String::from_utf8_lossy(&body).to_string() });
+    let head = format!("HTTP/1.1 {} Stub\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, content.len());
+    stream.write_all(head.as_bytes()).ok()?;
+    stream.write_all(&content).ok()
+}
@@ -0,0 +1,150 @@
+use encoding_rs::{EncoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Line endings of the rendered text files (`eol` in `.template`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Eol {
+    Lf,
+    Crlf,
+    /// CRLF on Windows, LF elsewhere
+    Native,
+    /// Keep the line endings of the template files
+    #[default]
+    Preserve,
+}
+
+impl Eol {
+    pub fn parse(value: &str) -> Option<Eol> {
+        match value.to_lowercase().as_str() {
+            "lf" => Some(Eol::Lf),
+            "crlf" => Some(Eol::Crlf),
+            "native" => Some(Eol::Native),
+            "preserve" => Some(Eol::Preserve),
+            _ => None,
+        }
+    }
+
+    /// `eol` value of `.gitattributes`, if the policy fixes the line endings
+    pub fn git_attribute(&self) -> Option<&'static str> {
+        match self {
+            Eol::Lf => Some("lf"),
+            Eol::Crlf => Some("crlf"),
+            Eol::Native | Eol::Preserve => None,
+        }
+    }
+
+    pub fn apply(&self, text: String) -> String {
+        let crlf = match self {
+            Eol::Preserve => return text,
+            Eol::Lf => false,
+            Eol::Crlf => true,
+            Eol::Native => cfg!(windows),
+        };
+        let lf = if text.contains('\r') { text.replace("\r\n", "\n") } else { text };
+        if crlf {
+            lf.replace('\n', "\r\n")
+        } else {
+            lf
+        }
+    }
+}
+
+/// A text file decoded for substitution, re-encoded as it was read
+pub struct DecodedText {
+    pub text: String,
+    encoding: &'static Encoding,
+    bom: bool,
+}
+
+impl DecodedText {
+    /// Decode a file. A byte order mark wins over the declared encoding; without
+    /// declaration the file must be valid UTF-8. Returns `None` for binary content.
+    pub fn decode(bytes: &[u8], declared: Option<&'static Encoding>) -> Option<DecodedText> {
+        if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+            let text = encoding
+                .decode_without_bom_handling_and_without_replacement(&bytes[bom_len..])?
+                .into_owned();
+            return Some(DecodedText { text, encoding, bom: true });
+        }
+        if bytes.contains(&0) {
+            return None;
+        }
+        let encoding = declared.unwrap_or(UTF_8);
+        let text = encoding.decode_without_bom_handling_and_without_replacement(bytes)?.into_owned();
+        Some(DecodedText { text, encoding, bom: false })
+    }
+
+    /// Decode a file that must be rendered whatever its content: malformed
+    /// sequences are replaced instead of making the file binary.
+    pub fn decode_lossy(bytes: &[u8], declared: Option<&'static Encoding>) -> DecodedText {
+        if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+            let text = encoding.decode_without_bom_handling(&bytes[bom_len..]).0.into_owned();
+            return DecodedText { text, encoding, bom: true };
+        }
+        let encoding = declared.unwrap_or(UTF_8);
+        let text = encoding.decode_without_bom_handling(bytes).0.into_owned();
+        DecodedText { text, encoding, bom: false }
+    }
+
+    /// Encode `text` back with the encoding and byte order mark of the original file.
+    /// Characters the encoding cannot represent are written as Java `\uXXXX` escapes.
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(text.len() + 3);
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            if self.bom {
+                bytes.extend_from_slice(if self.encoding == UTF_16LE { &[0xff, 0xfe] } else { &[0xfe, 0xff] });
+            }
+            for unit in text.encode_utf16() {
+                let pair = if self.encoding == UTF_16LE { unit.to_le_bytes() } else { unit.to_be_bytes() };
+                bytes.extend_from_slice(&pair);
+            }
+            return bytes;
+        }
+        if self.encoding == UTF_8 {
+            if self.bom {
+                bytes.extend_from_slice(&[0xef, 0xbb, 0xbf]);
+            }
+            bytes.extend_from_slice(text.as_bytes());
+            return bytes;
+        }
+
+        let mut encoder = self.encoding.new_encoder();
+        let mut input = text;
+        let mut buffer = [0u8; 4096];
+        loop {
+            let (result, read, written) = encoder.encode_from_utf8_without_replacement(input, &mut buffer, true);
+            bytes.extend_from_slice(&buffer[..written]);
+            input = &input[read..];
+            match result {
+                EncoderResult::InputEmpty => return bytes,
+                EncoderResult::OutputFull => {}
+                EncoderResult::Unmappable(c) => {
+                    let mut units = [0u16; 2];
+                    for unit in c.encode_utf16(&mut units) {
+                        bytes.extend_from_slice(format!("\\u{:04X}", unit).as_bytes());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether the first bytes of a file show it cannot be decoded as text, so it
+/// can be copied without being read whole. A sequence cut at the end of
+/// `prefix` does not count; encodings other than UTF-8 are only known at the end.
+pub fn looks_binary(prefix: &[u8], declared: Option<&'static Encoding>) -> bool {
+    if Encoding::for_bom(prefix).is_some() {
+        return false;
+    }
+    if prefix.contains(&0) {
+        return true;
+    }
+    match declared {
+        Some(encoding) if encoding != UTF_8 => false,
+        _ => std::str::from_utf8(prefix).is_err_and(|err| err.error_len().is_some()),
+    }
+}
+
+/// Encoding for a label such as `ISO-8859-1`, `windows-1252` or `UTF-8`
+pub fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+}
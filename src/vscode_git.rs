@@ -1,111 +1,573 @@
 use crate::cli::Cli;
-use crate::log::{log_success, log_verbose};
-use git2::Repository;
-use serde_json::json;
-use std::fs::{create_dir_all, write};
+use crate::fs::append_missing_lines;
+use crate::log::{log_info, log_success, log_verbose};
+use crate::placeholder::{Delimiters, Renderer};
+use crate::progress::TransferProgress;
+use git2::{Cred, CredentialType, Direction, PushOptions, RemoteCallbacks, Repository, RepositoryInitOptions};
+use std::cell::RefCell;
+use std::env;
+use std::fs::{read_dir, write};
 use std::io;
-use std::path::Path;
-
-pub fn setup_vscode_and_git(dest: &Path, cli: &Cli) -> io::Result<()> {
-    // VSCode config
-    log_verbose("Creating VSCode configuration", cli.verbose);
-    let vscode_dir = dest.join(".vscode");
-    create_dir_all(&vscode_dir)?;
-
-    let settings = json!({
-        "java.format.settings.url": ".vscode/java-formatter.xml",
-        "java.project.sourcePaths": ["src/main/java","src/main/resources","src/test/java","src/test/resources"],
-        "java.project.encoding": "warning",
-        "java.project.outputPath": "target/classes"
-    });
-    write(
-        vscode_dir.join("settings.json"),
-        serde_json::to_string_pretty(&settings)?,
-    )?;
-    log_success(".vscode/settings.json created");
-
-    let launch = json!({
-        "version": "0.2.0",
-        "configurations": [
-            {
-                "type": "java",
-                "name": "Run",
-                "request": "launch",
-                "mainClass": cli.mainclass,
-                "projectName": cli.project_name
-            }
-        ]
-    });
-    write(
-        vscode_dir.join("launch.json"),
-        serde_json::to_string_pretty(&launch)?,
-    )?;
-    log_success(".vscode/launch.json created");
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// `.gitignore` patterns of every project
+const IGNORED: [&str; 6] = ["*.class", "*.log", "out/", "bin/", ".idea/workspace.xml", ".DS_Store"];
+/// `.gitignore` patterns of the build outputs
+const IGNORED_MAVEN: [&str; 1] = ["target/"];
+const IGNORED_GRADLE: [&str; 2] = [".gradle/", "build/"];
+/// `.gitattributes` lines for files that must never be diffed or converted
+const BINARY_ATTRIBUTES: [&str; 10] = [
+    "*.jar binary",
+    "*.class binary",
+    "*.png binary",
+    "*.jpg binary",
+    "*.jpeg binary",
+    "*.gif binary",
+    "*.ico binary",
+    "*.webp binary",
+    "*.ttf binary",
+    "*.zip binary",
+];
+
+fn git_error(e: git2::Error) -> io::Error {
+    io::Error::other(e.message().to_string())
+}
+
+/// Reject a `--branch` that git would refuse, before anything is generated
+pub fn check_branch_name(name: &str) -> io::Result<()> {
+    if git2::Branch::name_is_valid(name).unwrap_or(false) {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid branch name: {}", name)))
+    }
+}
+
+pub fn setup_vscode_and_git(dest: &Path, cli: &Cli, replacements: &[(&str, &str)]) -> io::Result<()> {
+    crate::ide::setup_ides(dest, cli)?;
+    write_git_files(dest, cli)?;
+
+    if cli.no_git {
+        log_verbose("Git repository not created (--no-git)", cli.verbose);
+        return Ok(());
+    }
+
+    if let Ok(repo) = Repository::discover(dest) {
+        if repo.workdir().is_some() {
+            return add_to_enclosing_repository(&repo, dest, cli, replacements);
+        }
+    }
 
     // Initialize Git
-    log_verbose("Initializing Git repository", cli.verbose);
-    let repo = Repository::init(dest)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
-
-    let mut config = repo
-        .config()
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
-    config
-        .set_str("init.defaultBranch", "main")
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
-    config
-        .set_str("user.name", &cli.author)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
-    config
-        .set_str("user.email", &cli.email)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
-
-    let mut index = repo
-        .index()
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
+    log_verbose(&format!("Initializing Git repository on branch {}", cli.branch), cli.verbose);
+    let mut options = RepositoryInitOptions::new();
+    options.initial_head(&cli.branch);
+    let repo = Repository::init_opts(dest, &options).map_err(git_error)?;
+
+    let mut config = repo.config().map_err(git_error)?;
+    config.set_str("user.name", &cli.author).map_err(git_error)?;
+    config.set_str("user.email", &cli.email).map_err(git_error)?;
+
+    let mut index = repo.index().map_err(git_error)?;
     index
         .add_all(["."].iter(), git2::IndexAddOption::DEFAULT, None)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
+        .map_err(git_error)?;
+    index.write().map_err(git_error)?;
+    if cli.no_commit {
+        log_success(&format!("Git repository initialized on {}, project staged (--no-commit)", cli.branch));
+        return Ok(());
+    }
+
+    let tree_id = index.write_tree().map_err(git_error)?;
+    let tree = repo.find_tree(tree_id).map_err(git_error)?;
+    let sig = repo.signature().map_err(git_error)?;
+    let (message, _) = Renderer::new(replacements, &Delimiters::default()).render(&cli.commit_message);
+    repo.commit(Some("HEAD"), &sig, &sig, &message, &tree, &[])
+        .map_err(git_error)?;
+
+    log_success(&format!("Git repository initialized with initial commit on {}", cli.branch));
+
+    if let Some(url) = &cli.remote_git {
+        add_origin(&repo, url, cli.verbose)?;
+    }
+
+    Ok(())
+}
+
+/// Work tree of the Git repository that will contain `dest`, found from its
+/// nearest existing folder
+pub fn enclosing_repository(dest: &Path) -> Option<PathBuf> {
+    let existing = dest.ancestors().find(|path| path.is_dir())?;
+    let repo = Repository::discover(existing).ok()?;
+    repo.workdir().map(|workdir| workdir.components().collect())
+}
+
+/// Check the Git options against the repository enclosing the destination, if
+/// any, before the project is generated: remotes belong to new repositories,
+/// and `--scaffold-branch` needs an existing one.
+pub fn check_enclosing_repository(dest: &Path, cli: &Cli) -> io::Result<()> {
+    let Some(workdir) = enclosing_repository(dest) else {
+        if cli.scaffold_branch.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--scaffold-branch needs a destination inside an existing Git repository",
+            ));
+        }
+        return Ok(());
+    };
+    log_verbose(&format!("Destination is inside the Git repository {}", workdir.display()), cli.verbose);
+    if dest.is_dir() && dest.canonicalize()? == workdir.canonicalize()? {
+        return Err(work_tree_root(&workdir));
+    }
+    if cli.remote_git.is_some() || cli.create_remote.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is inside the Git repository {}: -r and --create-remote need a new repository", dest.display(), workdir.display()),
+        ));
+    }
+    if let Some(branch) = &cli.scaffold_branch {
+        check_branch_name(branch)?;
+        let repo = Repository::open(&workdir).map_err(git_error)?;
+        if repo.find_branch(branch, git2::BranchType::Local).is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("Branch {} already exists in {}", branch, workdir.display())));
+        }
+    }
+    Ok(())
+}
+
+/// The project cannot be told apart from the other files of the repository
+fn work_tree_root(workdir: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("The destination is the root of the Git repository {}: generate into a sub-folder, or use --no-git", workdir.display()),
+    )
+}
+
+/// Stage the project in the repository enclosing it instead of nesting a new
+/// one, then commit the project alone (other staged changes are left staged)
+/// on the current branch or on `--scaffold-branch`, unless `--no-commit`.
+fn add_to_enclosing_repository(repo: &Repository, dest: &Path, cli: &Cli, replacements: &[(&str, &str)]) -> io::Result<()> {
+    let workdir = repo.workdir().ok_or_else(|| io::Error::other("Bare repository"))?.canonicalize()?;
+    let relative = dest.canonicalize()?.strip_prefix(&workdir).map_err(io::Error::other)?.to_path_buf();
+    let pathspec = relative.to_string_lossy().replace('\\', "/");
+    if pathspec.is_empty() {
+        return Err(work_tree_root(&workdir));
+    }
+    log_info(&format!("Adding {} to the Git repository {}", dest.display(), workdir.display()));
+
+    let head = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    if let Some(branch) = &cli.scaffold_branch {
+        if let Some(commit) = &head {
+            repo.branch(branch, commit, false).map_err(git_error)?;
+        }
+        repo.set_head(&format!("refs/heads/{}", branch)).map_err(git_error)?;
+        log_success(&format!("Switched to new branch {}", branch));
+    }
+
+    let mut index = repo.index().map_err(git_error)?;
     index
-        .write()
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
-
-    let tree_id = index
-        .write_tree()
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
-    let tree = repo
-        .find_tree(tree_id)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
+        .add_all([pathspec.as_str()].iter(), git2::IndexAddOption::DISABLE_PATHSPEC_MATCH, None)
+        .map_err(git_error)?;
+    let prefix = format!("{}/", pathspec);
+    index.write().map_err(git_error)?;
+    if cli.no_commit {
+        log_success(&format!("Project staged in {} (--no-commit)", workdir.display()));
+        return Ok(());
+    }
+
+    // tree of HEAD plus the project, whatever else the index holds
+    let mut tree_index = git2::Index::new().map_err(git_error)?;
+    if let Some(commit) = &head {
+        tree_index.read_tree(&commit.tree().map_err(git_error)?).map_err(git_error)?;
+    }
+    for entry in index.iter().filter(|entry| entry.path.starts_with(prefix.as_bytes())) {
+        tree_index.add(&entry).map_err(git_error)?;
+    }
+    let tree_id = tree_index.write_tree_to(repo).map_err(git_error)?;
+    let tree = repo.find_tree(tree_id).map_err(git_error)?;
+
     let sig = repo
         .signature()
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
-    repo.commit(
-        Some("HEAD"),
-        &sig,
-        &sig,
-        &format!("Create Project {}", cli.project_name),
-        &tree,
-        &[],
-    )
-    .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
+        .or_else(|_| git2::Signature::now(&cli.author, &cli.email))
+        .map_err(git_error)?;
+    let (message, _) = Renderer::new(replacements, &Delimiters::default()).render(&cli.commit_message);
+    let parents: Vec<&git2::Commit> = head.iter().collect();
+    repo.commit(Some("HEAD"), &sig, &sig, &message, &tree, &parents).map_err(git_error)?;
 
-    log_success("Git repository initialized with initial commit");
+    let branch = repo.head().ok().and_then(|head| head.shorthand().map(str::to_string)).unwrap_or_default();
+    log_success(&format!("Project committed on {} in {}", branch, workdir.display()));
+    Ok(())
+}
 
-    if let Some(url) = &cli.remote_git {
-        log_verbose(
-            &format!("Configuring remote repository: {}", url),
-            cli.verbose,
-        );
-        repo.remote("origin", url)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
-        let mut remote = repo
-            .find_remote("origin")
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
-        remote
-            .push(&["refs/heads/main:refs/heads/main"], None)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.message().to_string()))?;
-        log_success("Pushed to remote repository");
+/// Set `url` as the `origin` remote of the repository
+fn add_origin(repo: &Repository, url: &str, verbose: bool) -> io::Result<()> {
+    let url = remote_url(url)?;
+    log_verbose(&format!("Configuring remote repository: {}", url), verbose);
+    repo.remote("origin", &url).map_err(git_error)?;
+    log_success(&format!("Remote origin set to {}", url));
+    Ok(())
+}
+
+/// Set the repository created on a forge as `origin` of the generated project
+pub fn set_origin(dest: &Path, url: &str, verbose: bool) -> io::Result<()> {
+    let repo = Repository::open(dest).map_err(git_error)?;
+    add_origin(&repo, url, verbose)
+}
+
+/// URL of a remote as stored in the repository: local paths are made absolute,
+/// since git resolves relative ones against the current directory.
+fn remote_url(url: &str) -> io::Result<String> {
+    if url.contains("://") || is_scp_like(url) {
+        return Ok(url.to_string());
+    }
+    let path = Path::new(url);
+    if !path.exists() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("No repository at {}", url)));
     }
+    Ok(std::fs::canonicalize(path)?.to_string_lossy().to_string())
+}
+
+/// `user@host:path` SSH remotes (a colon before any slash, not a drive letter)
+fn is_scp_like(url: &str) -> bool {
+    match (url.find(':'), url.find('/')) {
+        (Some(colon), slash) => colon > 1 && slash.is_none_or(|slash| colon < slash),
+        (None, _) => false,
+    }
+}
+
+/// Credentials for a remote, tried in order until the server accepts one:
+/// SSH agent, then the key in `GENJ_SSH_KEY` or `~/.ssh` (passphrase in
+/// `GENJ_SSH_PASSPHRASE`); for HTTPS, the token in `GENJ_GIT_TOKEN`, then the
+/// git credential helper. `token` (user name and token of a forge) is tried
+/// first for HTTPS.
+fn remote_callbacks<'a>(verbose: bool, token: Option<(&'a str, &'a str)>) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+    let mut tried: Vec<String> = Vec::new();
+
+    callbacks.credentials(move |url, username_from_url, allowed| {
+        let username = username_from_url.unwrap_or("git");
+        let mut attempt = |method: String| {
+            let first = !tried.contains(&method);
+            if first {
+                log_verbose(&format!("Trying {} for {}", method, url), verbose);
+                tried.push(method);
+            }
+            first
+        };
 
+        if allowed.contains(CredentialType::SSH_KEY) {
+            if attempt("ssh-agent".to_string()) {
+                return Cred::ssh_key_from_agent(username);
+            }
+            let passphrase = env::var("GENJ_SSH_PASSPHRASE").ok();
+            for key in ssh_keys() {
+                if attempt(format!("SSH key {}", key.display())) {
+                    return Cred::ssh_key(username, None, &key, passphrase.as_deref());
+                }
+            }
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some((user, token)) = token {
+                if attempt("forge token".to_string()) {
+                    return Cred::userpass_plaintext(user, token);
+                }
+            }
+            if let Ok(token) = env::var("GENJ_GIT_TOKEN") {
+                if attempt("GENJ_GIT_TOKEN".to_string()) {
+                    return Cred::userpass_plaintext(username, &token);
+                }
+            }
+            if attempt("git credential helper".to_string()) {
+                let config = git2::Config::open_default()?;
+                return Cred::credential_helper(&config, url, username_from_url);
+            }
+        }
+        if allowed.contains(CredentialType::USERNAME) && attempt("username".to_string()) {
+            return Cred::username(username);
+        }
+        if allowed.contains(CredentialType::DEFAULT) && attempt("default credentials".to_string()) {
+            return Cred::default();
+        }
+        Err(git2::Error::from_str(&format!(
+            "no accepted credentials for {} (tried: {})",
+            url,
+            if tried.is_empty() { "none".to_string() } else { tried.join(", ") }
+        )))
+    });
+    callbacks
+}
+
+/// SSH private keys to try: `GENJ_SSH_KEY`, else the default keys of `~/.ssh`
+fn ssh_keys() -> Vec<PathBuf> {
+    if let Ok(key) = env::var("GENJ_SSH_KEY") {
+        return vec![PathBuf::from(key)];
+    }
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    ["id_ed25519", "id_ecdsa", "id_rsa"]
+        .iter()
+        .map(|name| home.join(".ssh").join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Connect to the remote for a push and authenticate, so that a `--push` that
+/// cannot work fails before the project is generated
+pub fn check_remote(url: &str, verbose: bool) -> io::Result<()> {
+    let url = remote_url(url)?;
+    log_verbose(&format!("Checking access to {}", url), verbose);
+    let mut remote = git2::Remote::create_detached(url.as_str()).map_err(git_error)?;
+    remote
+        .connect_auth(Direction::Push, Some(remote_callbacks(verbose, None)), None)
+        .map_err(|e| io::Error::other(format!("Cannot push to {}: {}", url, e.message())))?;
+    remote.disconnect().map_err(git_error)?;
     Ok(())
 }
+
+/// Push the initial branch to `origin` and make it the upstream branch;
+/// `token` is the user name and token of the forge that created `origin`
+pub fn push_to_remote(dest: &Path, cli: &Cli, token: Option<(&str, &str)>) -> io::Result<()> {
+    let repo = Repository::open(dest).map_err(git_error)?;
+    let mut remote = repo.find_remote("origin").map_err(git_error)?;
+    log_info(&format!("Pushing {} to {}", cli.branch, remote.url().unwrap_or("origin")));
+
+    let progress = TransferProgress::new(cli.verbose);
+    let rejected: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    let mut callbacks = remote_callbacks(cli.verbose, token);
+    callbacks.push_transfer_progress(|current, total, bytes| progress.update(current, total, bytes));
+    callbacks.push_update_reference(|reference, status| {
+        if let Some(message) = status {
+            rejected.borrow_mut().push(format!("{}: {}", reference, message));
+        }
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+
+    let refspec = format!("refs/heads/{0}:refs/heads/{0}", cli.branch);
+    let result = remote.push(&[refspec.as_str()], Some(&mut options));
+    progress.finish();
+    result.map_err(|e| io::Error::other(format!("Push failed: {}", e.message())))?;
+    let rejected = rejected.take();
+    if !rejected.is_empty() {
+        return Err(io::Error::other(format!("Push rejected: {}", rejected.join(", "))));
+    }
+
+    if let Ok(mut branch) = repo.find_branch(&cli.branch, git2::BranchType::Local) {
+        if let Err(e) = branch.set_upstream(Some(&format!("origin/{}", cli.branch))) {
+            log_verbose(&format!("Upstream branch not set: {}", e.message()), cli.verbose);
+        }
+    }
+    log_success("Pushed to remote repository");
+    Ok(())
+}
+
+/// `.gitignore` for the build tool and `.gitattributes` for binary files, merged
+/// with those of the template, and a `.gitkeep` in every empty folder so that
+/// folders such as `src/test/resources` survive a clone.
+fn write_git_files(dest: &Path, cli: &Cli) -> io::Result<()> {
+    let build_ignored: &[&str] = if cli.build_tool.eq_ignore_ascii_case("gradle") { &IGNORED_GRADLE } else { &IGNORED_MAVEN };
+    let ignored: Vec<String> = build_ignored.iter().chain(IGNORED.iter()).map(|p| p.to_string()).collect();
+    if append_missing_lines(&dest.join(".gitignore"), &ignored)? {
+        log_success(".gitignore updated");
+    }
+
+    let attributes: Vec<String> = BINARY_ATTRIBUTES.iter().map(|a| a.to_string()).collect();
+    if append_missing_lines(&dest.join(".gitattributes"), &attributes)? {
+        log_success(".gitattributes updated");
+    }
+
+    let empty_dirs: Vec<PathBuf> = WalkDir::new(dest)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_dir())
+        .filter(|e| read_dir(e.path()).is_ok_and(|mut entries| entries.next().is_none()))
+        .map(|e| e.into_path())
+        .collect();
+    for dir in empty_dirs {
+        write(dir.join(".gitkeep"), "")?;
+        log_verbose(&format!("Added {}", dir.join(".gitkeep").display()), cli.verbose);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    /// Generate a project committing `content`, with `url` as origin
+    fn project(dir: &Path, content: &str, url: &str) -> (PathBuf, Cli) {
+        let dest = dir.join("project");
+        std::fs::create_dir_all(&dest).unwrap();
+        write(dest.join("README.md"), content).unwrap();
+        let cli = Cli::try_parse_from(["genj", "-r", url, "--push"]).unwrap();
+        setup_vscode_and_git(&dest, &cli, &[]).unwrap();
+        (dest, cli)
+    }
+
+    fn bare_repository(dir: &Path) -> (PathBuf, Repository) {
+        let path = dir.join("remote.git");
+        let repo = Repository::init_bare(&path).unwrap();
+        (path, repo)
+    }
+
+    fn assert_pushed(dest: &Path, remote: &Repository) {
+        let local = Repository::open(dest).unwrap();
+        let head = local.head().unwrap().target().unwrap();
+        assert_eq!(remote.refname_to_id("refs/heads/main").unwrap(), head);
+        let branch = local.find_branch("main", git2::BranchType::Local).unwrap();
+        assert_eq!(branch.upstream().unwrap().name().unwrap(), Some("origin/main"));
+    }
+
+    /// A repository with one commit and `staged.txt` staged but not committed
+    fn enclosing_repository_with_staged_file(dir: &Path) -> Repository {
+        let repo = Repository::init(dir).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Tester").unwrap();
+        config.set_str("user.email", "tester@example.com").unwrap();
+        write(dir.join("committed.txt"), "committed").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("committed.txt")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = repo.signature().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[]).unwrap();
+        drop(tree);
+
+        write(dir.join("staged.txt"), "unrelated").unwrap();
+        index.add_path(Path::new("staged.txt")).unwrap();
+        index.write().unwrap();
+        repo
+    }
+
+    #[test]
+    fn project_in_a_sub_folder_is_committed_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = enclosing_repository_with_staged_file(dir.path());
+        let dest = dir.path().join("Demo");
+        std::fs::create_dir_all(&dest).unwrap();
+        write(dest.join("README.md"), "demo").unwrap();
+        let cli = Cli::try_parse_from(["genj"]).unwrap();
+        check_enclosing_repository(&dest, &cli).unwrap();
+        setup_vscode_and_git(&dest, &cli, &[]).unwrap();
+
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        assert!(tree.get_path(Path::new("Demo/README.md")).is_ok());
+        assert!(tree.get_path(Path::new("staged.txt")).is_err());
+        // still staged
+        assert!(repo.index().unwrap().get_path(Path::new("staged.txt"), 0).is_some());
+    }
+
+    #[test]
+    fn work_tree_root_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = enclosing_repository_with_staged_file(dir.path());
+        let head = repo.head().unwrap().target();
+        write(dir.path().join("README.md"), "demo").unwrap();
+        let cli = Cli::try_parse_from(["genj"]).unwrap();
+
+        let error = check_enclosing_repository(dir.path(), &cli).unwrap_err();
+        assert!(error.to_string().contains("root of the Git repository"), "{}", error);
+        assert!(setup_vscode_and_git(dir.path(), &cli, &[]).is_err());
+        assert_eq!(repo.head().unwrap().target(), head);
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        assert!(tree.get_path(Path::new("staged.txt")).is_err());
+    }
+
+    #[test]
+    fn push_to_a_local_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, remote) = bare_repository(dir.path());
+        let (dest, cli) = project(dir.path(), "demo", path.to_str().unwrap());
+        push_to_remote(&dest, &cli, None).unwrap();
+        assert_pushed(&dest, &remote);
+    }
+
+    #[test]
+    fn push_to_a_file_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, remote) = bare_repository(dir.path());
+        let (dest, cli) = project(dir.path(), "demo", &format!("file://{}", path.display()));
+        push_to_remote(&dest, &cli, None).unwrap();
+        assert_pushed(&dest, &remote);
+    }
+
+    #[test]
+    fn non_fast_forward_push_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, _remote) = bare_repository(dir.path());
+        let (dest, cli) = project(dir.path(), "first", path.to_str().unwrap());
+        push_to_remote(&dest, &cli, None).unwrap();
+
+        // an unrelated history on the same branch
+        let other = dir.path().join("other");
+        let (dest, cli) = project(&other, "second", path.to_str().unwrap());
+        let error = push_to_remote(&dest, &cli, None).unwrap_err();
+        assert!(error.to_string().contains("Push failed"), "{}", error);
+        assert!(error.to_string().contains("not present locally"), "{}", error);
+    }
+
+    #[test]
+    fn reference_refused_by_the_remote_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, _remote) = bare_repository(dir.path());
+        // the remote cannot lock the branch: libgit2 reports it through push_update_reference
+        write(path.join("refs/heads/main.lock"), "").unwrap();
+        let (dest, cli) = project(dir.path(), "demo", path.to_str().unwrap());
+        let error = push_to_remote(&dest, &cli, None).unwrap_err();
+        assert!(error.to_string().starts_with("Push rejected: refs/heads/main: "), "{}", error);
+    }
+
+    #[test]
+    fn git_files_are_merged_with_those_of_the_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path();
+        write(dest.join(".gitignore"), "custom/\n*.log").unwrap();
+        std::fs::create_dir_all(dest.join("src/test/resources")).unwrap();
+        std::fs::create_dir_all(dest.join("src/main/java")).unwrap();
+        write(dest.join("src/main/java/App.java"), "class App {}").unwrap();
+        std::fs::create_dir_all(dest.join(".git/refs")).unwrap();
+        let cli = Cli::try_parse_from(["genj", "-b", "gradle"]).unwrap();
+        write_git_files(dest, &cli).unwrap();
+
+        let gitignore = std::fs::read_to_string(dest.join(".gitignore")).unwrap();
+        assert_eq!(gitignore, "custom/\n*.log\n.gradle/\nbuild/\n*.class\nout/\nbin/\n.idea/workspace.xml\n.DS_Store\n");
+        let gitattributes = std::fs::read_to_string(dest.join(".gitattributes")).unwrap();
+        assert_eq!(gitattributes, BINARY_ATTRIBUTES.join("\n") + "\n");
+
+        assert!(dest.join("src/test/resources/.gitkeep").is_file());
+        assert!(!dest.join("src/main/java/.gitkeep").exists());
+        assert!(!dest.join(".git/refs/.gitkeep").exists());
+
+        // a second run adds nothing
+        write_git_files(dest, &cli).unwrap();
+        assert_eq!(std::fs::read_to_string(dest.join(".gitignore")).unwrap(), gitignore);
+        let cli = Cli::try_parse_from(["genj"]).unwrap();
+        write_git_files(dest, &cli).unwrap();
+        assert!(std::fs::read_to_string(dest.join(".gitignore")).unwrap().ends_with(".DS_Store\ntarget/\n"));
+    }
+
+    #[test]
+    fn initial_commit_is_on_the_requested_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("Demo");
+        std::fs::create_dir_all(&dest).unwrap();
+        write(dest.join("README.md"), "demo").unwrap();
+        let cli = Cli::try_parse_from(["genj", "--ide", "none", "--branch", "trunk", "--commit-message", "Start ${PROJECT_NAME}"]).unwrap();
+        setup_vscode_and_git(&dest, &cli, &[("${PROJECT_NAME}", "Demo")]).unwrap();
+
+        let repo = Repository::open(&dest).unwrap();
+        let head = repo.head().unwrap();
+        assert_eq!(head.name(), Some("refs/heads/trunk"));
+        let commit = head.peel_to_commit().unwrap();
+        assert_eq!(commit.message(), Some("Start Demo"));
+        let tree = commit.tree().unwrap();
+        for file in ["README.md", ".gitignore", ".gitattributes"] {
+            assert!(tree.get_name(file).is_some(), "{}", file);
+        }
+        assert!(check_branch_name("feature..x").is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+/// The `genj` binary run in `dir`, with a home folder of its own
+fn command(dir: &Path, args: &[&str]) -> Command {
+    let home = dir.join("home");
+    fs::create_dir_all(&home).unwrap();
+    let mut command = Command::new(env!("CARGO_BIN_EXE_genj"));
+    command
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("XDG_CACHE_HOME", home.join(".cache"));
+    command
+}
+
+fn genj(dir: &Path, args: &[&str]) -> Output {
+    command(dir, args).output().unwrap()
+}
+
+#[test]
+fn failed_git_setup_with_push_fails_the_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let template = dir.path().join("tpl");
+    fs::create_dir_all(&template).unwrap();
+    fs::write(template.join("a.txt"), "hi").unwrap();
+    // a file where the IDE setup creates a folder
+    fs::write(template.join(".vscode"), "not a folder").unwrap();
+    git2::Repository::init_bare(dir.path().join("remote.git")).unwrap();
+
+    let output = genj(dir.path(), &["-t", "tpl", "-d", "out", "--project_name", "Demo", "-r", "remote.git", "--push"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("IDE/Git configuration failed"), "{}", stderr);
+
+    // without a remote step the project is still generated, with a warning
+    let output = genj(dir.path(), &["-t", "tpl", "-d", "out2", "--project_name", "Demo"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn reader_closing_the_pipe_ends_the_list_quietly() {
+    let dir = tempfile::tempdir().unwrap();
+    // more output than a pipe buffers
+    let description = "x".repeat(200);
+    for i in 0..500 {
+        let template = dir.path().join(format!("home/.genj/template-{}", i));
+        fs::create_dir_all(&template).unwrap();
+        fs::write(template.join(".template"), format!(r#"{{"description": "{}"}}"#, description)).unwrap();
+    }
+
+    for format in ["plain", "json"] {
+        let mut child = command(dir.path(), &["--list", "--format", format])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut first = String::new();
+        BufReader::new(child.stdout.take().unwrap()).read_line(&mut first).unwrap();
+        assert!(!first.is_empty());
+
+        let mut stderr = String::new();
+        child.stderr.take().unwrap().read_to_string(&mut stderr).unwrap();
+        assert!(child.wait().unwrap().success(), "{}", stderr);
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+}
+
+#[test]
+fn internal_github_repository_needs_an_owner() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("tpl")).unwrap();
+    fs::write(dir.path().join("tpl/a.txt"), "hi").unwrap();
+
+    let args = ["-t", "tpl", "-d", "out", "--project_name", "Demo", "--create-remote", "github", "--remote-visibility", "internal"];
+    let output = genj(dir.path(), &args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--remote-owner"), "{}", stderr);
+    // refused before any generation or forge call
+    assert!(!dir.path().join("out").exists());
+}